@@ -1,12 +1,28 @@
 //! Generate io::Error style error.
+//!
+//! # Features
+//!
+//! The optional features only enable the attributes generating impls for third party crates.
+//! kinderror doesn't depend on those crates, the generated code names them by path, so each one
+//! must also be a dependency of the crate using the attribute:
+//!
+//! - `serde`: `serde`, for the `serde` attribute
+//! - `schemars`: `schemars` 1.x, for the `json_schema` attribute
+//! - `utoipa`: `utoipa` 5.x, for the `utoipa` attribute
+//! - `miette`: `miette`, for the `miette` attribute
+//! - `defmt`: `defmt`, for the `defmt` attribute
+//! - `tracing`: `tracing-error`, for the `span_trace` attribute
+//! - `arbitrary`: `arbitrary`, for the `arbitrary` attribute
 
 extern crate proc_macro;
 use proc_macro::TokenStream;
-use quote::quote;
+use quote::{ToTokens, format_ident, quote};
 use syn::{
-    Attribute, Data, DeriveInput, Ident, Meta, Token, Type, Visibility,
+    Attribute, Data, DeriveInput, Ident, Lifetime, Meta, Token, Type, Visibility, WherePredicate,
+    ext::IdentExt as _,
     parse::{Parse, ParseStream},
-    parse_macro_input,
+    parse_macro_input, parse_quote,
+    punctuated::Punctuated,
 };
 
 /// Generate io::Error style error.
@@ -38,16 +54,116 @@ use syn::{
 /// assert!(err.source().is_some());
 /// ```
 ///
+/// A `#[cfg(...)]` on the enum gates every generated item as well, since the derive only runs when the enum is compiled.
+///
 /// # Attributes
 ///
-/// - `source`: (required) source error type, e.g. `"std::io::Error"`
-/// - `source_fn`: (default: true) whether to implement the `Error::source()` method. Set to `false` when the source type does not implement `dyn ::core::error::Error + 'static`
+/// - `source`: (default: none) source error type, e.g. `"std::io::Error"`. When it names a type parameter of the enum, e.g. `"S"`, the Display and Error impls are bounded by `S: Error + 'static`, or by `S: Debug` when `source_fn` is false. `new(kind, source)` takes the source as `impl Into<Source>`, so anything convertible can be passed without `.into()`. Without it the struct only wraps the kind: `new(kind)` takes no source, no `origin()` or `Error::source()` is generated, and the default Display only formats the kind
+/// - `source_fn`: (default: true) whether to implement the `Error::source()` method. For a boxed trait object source like `Box<dyn Error + Send + Sync>`, the boxed error itself is returned, and a `wrap(kind, err)` constructor (visibility from `new_vis`) re-classifies any other error, including another kind error, under a new kind. Outside `sources` mode, `downcast_source_ref()`, `downcast_source_mut()` and the consuming `downcast_source()` (visibility from `origin_fn_vis`) recover the concrete source type. Set to `false` when the source type does not implement `dyn ::core::error::Error + 'static`; such a source is reported with a hint pointing here
 /// - `new_vis`: (default: inherited) visibility of the constructor, e.g. `"pub"`
+/// - `new_fn`: (default: true) whether to generate `new()`. When false, only the `From` impls and the other enabled constructors build the error
 /// - `name`: (default: "Error") name of the generated error struct
 /// - `type_vis`: (default: inherited) visibility of the struct
+/// - `fields_vis`: (default: inherited) visibility of the struct fields, e.g. `"pub(crate)"` to pattern match on or build the struct directly
+/// - `kind_field`, `source_field`: (default: "kind", "source", or "sources" in `sources` mode) names of the struct fields holding the kind and the source, also used by the derived `Debug`
+/// - `kind_fn_name`, `origin_fn_name`, `new_fn_name`: (default: "kind", "origin", "new") names of the `kind()`, `origin()` and `new()` methods, e.g. to avoid collisions with other inherent methods
 /// - `kind_fn_vis`: (default: pub) visibility of the `kind()` method
+/// - `is_fns`: (default: false) whether to generate an `is_<variant>()` method (visibility from `kind_fn_vis`, name in snake_case unless `rename_all` says otherwise) per variant, e.g. `err.is_timeout()`
+/// - `kind_is_fns`: (default: false) whether to generate the same `is_<variant>()` methods on the kind enum
+/// - `as_fns`: (default: false) whether to generate an `as_<variant>()` method (visibility from `kind_fn_vis`, name in snake_case unless `rename_all` says otherwise) per data-carrying variant, returning a reference to its field, or a tuple of references to its fields, when the error is of that variant
+/// - `kind_by_value`: (default: false) whether `kind()` returns the kind by value instead of by reference, like `io::Error::kind()`. Requires the kind to implement `Copy`
 /// - `origin_fn_vis`: (default: pub) visibility of the `origin()` method
 /// - `display`: (default: "error kind: {kind:?}, source: {source:?}") custom Display format. Supports placeholders `{kind}` and `{source}`, users can freely specify format specifiers
+/// - `debug`: (default: none) custom Debug format replacing the derived `Debug`, with the same placeholders as `display`, e.g. `"{kind:?}: {source}"`. Can't be combined with `derive_debug = false`
+/// - `debug_chain`: (default: false) whether the alternate Debug (`{:#?}`, e.g. `dbg!`) prints the Display of the error followed by its source chain, one cause per line under `Caused by:`, instead of the nested struct. The plain Debug is unchanged, or follows `debug` if set. Can't be combined with `derive_debug = false`
+/// - `source_lifetime`: (default: none) lifetime parameter declared on the enum that the source borrows, e.g. `"'a"`. Since `Error::source()` requires a `'static` trait object, an inherent `source()` (visibility from `origin_fn_vis`) returning `Option<&(dyn Error + '_)>` is generated instead
+/// - `termination`: (default: false) whether to implement `std::process::Termination`, printing the error and its source chain to stderr and exiting with `ExitCode::FAILURE`. Requires `std`
+/// - `ord_by_kind`: (default: false) whether to implement `PartialEq`, `Eq`, `PartialOrd` and `Ord` comparing only the kind, ignoring the source. Requires the kind to implement `Ord`
+/// - `cmp_by_kind`: (default: false) whether to implement `PartialEq`, `Eq` and `Hash` comparing and hashing only the kind, ignoring the source, e.g. to dedup errors in a `HashSet`. Combine with `ord_by_kind` to also order them. Requires the kind to implement `Eq` and `Hash`
+/// - `delegate`: (default: none) `;` separated source methods forwarded by the struct, so they can be called without exposing `origin()`, e.g. `"pub fn raw_os_error(&self) -> Option<i32>; pub fn kind(&self) -> std::io::ErrorKind as io_kind"`. `as` renames the generated method. The full signature is needed because a derive macro only sees the name of the source type, not its methods, so it can't infer the arguments and return type from a bare method name. Requires a single, non-optional source
+/// - `as_ref_error`: (default: false) whether to implement `AsRef<dyn Error + Send + Sync + 'static>` and `AsRef<dyn Error + 'static>`, for APIs taking `impl AsRef<dyn Error>`. Requires the struct to be `Send + Sync + 'static`
+/// - `eq_kind`: (default: false) whether to implement `PartialEq<Kind>` for the struct and `PartialEq<{name}>` for the kind, comparing only the kind, e.g. `err == ErrorKind::First`. Requires the kind to implement `PartialEq`
+/// - `error_bounds`: (default: none) extra where-clause predicates appended to the generated `Error` impl, e.g. `"S: Send + Sync"`
+/// - `sources`: (default: false) whether to store multiple sources in a `Vec`. Generates `new(kind, sources)`, `push_source()` and `sources()` (visibility from `origin_fn_vis`) instead of `origin()`; `Error::source()` returns the first source, and `{source}` in `display` renders the number of sources
+/// - `code_ty`: (default: "u32") return type of the generated `code()` method, one of `u8`, `u16`, `u32` or `u64`
+/// - `skip_code`: (default: 0) code reported by `code()` for variants marked `skip`, e.g. `skip_code = 999`. No other variant may use it, so a skipped variant can't be mistaken for a real one. Requires a skipped variant
+/// - `from_code`: (default: true for fieldless enums) whether to generate the `from_code()` associated function. Enabling it when a variant carries fields is an error
+/// - `timestamp`: (default: false) whether to record the `Instant` the error was created at, exposed by `created_at()` and `elapsed()`, and the wall clock `SystemTime`, exposed by `occurred_at()`. Requires `std`
+/// - `timestamp_fn_vis`: (default: pub) visibility of the `created_at()`, `elapsed()` and `occurred_at()` methods
+/// - `clock_fn`: (default: none) function returning the `SystemTime` recorded by `timestamp` instead of `SystemTime::now()`, e.g. a mockable clock
+/// - `report_fn`: (default: none) associated function building a report type from the code and the Display message, e.g. `"Report::from_parts"`. Generates a `to_report()` method (visibility from `kind_fn_vis`) returning that type. Requires a `code` attribute on every variant
+/// - `derive_debug`: (default: true) whether to derive `Debug` for the struct. Set to `false` to provide a custom `Debug` impl, which `Error` requires, e.g. to redact secrets. `auto_debug` is accepted as an alias. The default Display only formats the kind and source with `Debug`, so it keeps working
+/// - `derive`: (default: none) comma separated list of extra derives for the struct, e.g. `"Clone, PartialEq"`, which the kind, source and other fields have to support. `Clone` can't be combined with `io_clone` or `arc_source`, which already implement it
+/// - `struct_attrs`: (default: none) outer attributes added to the struct after its derives, e.g. `"#[serde(rename_all = \"snake_case\")] #[repr(align(8))]"`. With `boxed` they only apply to the outer newtype
+/// - `non_exhaustive`: (default: false) whether to mark the struct `#[non_exhaustive]`, so other crates can't build or destructure it even with public `fields_vis`, and fields can be added later without a breaking change
+/// - `must_use`: (default: false) whether to mark the struct `#[must_use]`, so constructing an error and dropping it warns. The builder struct and `build()` are marked too
+/// - `const_new`: (default: false) whether to make `new()` a `const fn`. The source is then taken as is instead of `impl Into<Source>`. Can't be combined with `timestamp`
+/// - `const_fns`: (default: false) whether to make `kind()` and `origin()` `const fn` too. Implies `const_new`, so the same restrictions apply
+/// - `io_clone`: (default: false) whether to implement `Clone` by rebuilding the source from its `io::ErrorKind` and message. Requires the source to be `std::io::Error`, and the kind and `extra_fields` to implement `Clone`
+/// - `validate_fn`: (default: none) function called as `validate_fn(&kind, &source)`, or `validate_fn(&kind)` without a source, by `new()` before the error is built, e.g. to assert invariants. Can't be combined with `const_new`
+/// - `validate_try_fn`: (default: none) function called as `validate_try_fn(&kind, &source)`, or `validate_try_fn(&kind)` without a source, returning `Result<(), E>`. Generates a `try_new()` constructor (visibility from `new_vis`) returning `Result<Self, E>`
+/// - `validate_error`: (default: none) the error type `E` returned by `validate_try_fn`, required together with it
+/// - `discriminant`: (default: false) whether to generate a `kind_discriminant()` method (visibility from `kind_fn_vis`) returning the `Discriminant` of the kind, to compare kinds by variant only
+/// - `default_kind`: (default: none) kind used by the generated `From` impls, e.g. `"ErrorKind::Other"`. Without `from_sources`, `From<Source>` is implemented so `?` wraps the source under this kind. With a boxed `dyn Error` source, e.g. in `catch_all` mode, an `other(err)` constructor (visibility from `new_vis`) like `io::Error::other()` wraps anything convertible into the source, such as a message, under this kind
+/// - `builder`: (default: false) whether to generate a builder, returned by `builder()`, with `kind()`, `source()`, a setter per extra field, `message()` and `context()` when enabled, finished by `build()`. `build()` returns `None` when the kind or a required source is missing, and unset extra fields are defaulted
+/// - `builder_name`: (default: "{name}Builder") name of the generated builder struct
+/// - `kind_into`: (default: false) whether `new()` takes the kind as `impl Into<Kind>`, so smaller kinds converting into this one can be passed directly. Can't be combined with `const_new`
+/// - `variant_ctors`: (default: true) whether to generate a constructor per variant, named after it in snake case unless `rename_all` says otherwise, e.g. `Error::not_found(source)`. Data-carrying variants take their fields after the source. A constructor clashing with another generated method, e.g. `kind()` for a `Kind` variant, is an error unless the variant has an `alias` naming it instead. The `other()` generated for `default_kind` with a boxed source stands in for the constructor of that variant. Set to `false` to keep the API small
+/// - `classify_with`: (default: none) path of a `fn(&Source) -> Kind` used by the generated `from_source(source)` to pick the kind. Can't be combined with `sources` or per-variant source types
+/// - `source_default`: (default: none) source expression used when only the kind is known, e.g. `"io::Error::other(\"unknown\")"`. Generates `from_kind(kind)` and `impl From<Kind>`. Can't be combined with `optional_source`
+/// - `from_sources`: (default: none) comma separated error types to generate `From` impls for, e.g. `"io::Error, ParseIntError"`. Each one is converted into the source with `Into` and wrapped under `default_kind`, which is required
+/// - `inspect_fn`: (default: none) function called as `inspect_fn(&kind, &source)`, or `inspect_fn(&kind)` without a source, by every constructor once the error is built, e.g. to record metrics. Can't be combined with `const_new`
+/// - `all_kinds`: (default: false) whether to generate an `all_kinds()` associated function (visibility from `kind_fn_vis`) listing every variant. Requires every variant to be fieldless
+/// - `kind_fn`: (default: true) whether to generate the `kind()` method. The other generated methods keep working without it
+/// - `mut_fn_vis`: (default: pub(crate)) visibility of the `kind_mut()` and `source_mut()` methods, or `sources_mut()` in `sources` mode, to change an error in place. `source_mut()` isn't generated with per-variant source types
+/// - `replace_source_fn_vis`: (default: pub(crate)) visibility of the `replace_source()` method, which swaps the source in place and returns the previous one. Not generated in `sources` mode
+/// - `rename_all`: (default: none) case convention applied to variant names, one of `"snake_case"`, `"camelCase"` or `"SCREAMING_SNAKE_CASE"`. Generates a `kind_name()` method (visibility from `kind_fn_vis`) returning the renamed variant name. The names of the per-variant methods follow it too, e.g. `isNotFound()` and `notFound(source)` for `"camelCase"`
+/// - `legacy_error_methods`: (default: false) whether to implement the deprecated `Error::description()`, returning the variant name (renamed by `rename_all`), and `Error::cause()`, returning the same as `Error::source()`
+/// - `repr_transparent`: (default: false) whether to mark the struct `#[repr(transparent)]`. Only valid when the struct has a single field
+/// - `source_display_fallback`: (default: false) whether `{source}` in `display` renders the source with `Debug`, for sources that don't implement `Display`
+/// - `arbitrary`: (default: false) whether to implement `arbitrary::Arbitrary`, picking a variant uniformly and building its fields and the source with `Arbitrary`. Requires the `arbitrary` feature and a dependency on the `arbitrary` crate
+/// - `serde`: (default: false) whether to implement `serde::Serialize` and `serde::Deserialize`, writing the kind, the source rendered as a string, the `code()` if the variants have codes, the extra fields and the `message` and `context` fields if enabled. Deserializing rebuilds the error with `new()` and turns the source string back into an opaque error: `io::Error::other()` for `std::io::Error`, and `From<String>` for other sources, e.g. `Box<dyn Error + Send + Sync>`. The context is only serialized. Requires the `serde` feature and a dependency on the `serde` crate, and the kind and extra fields must implement both traits. Can't be combined with a generic kind enum, per-variant source types or `source_lifetime`
+/// - `json_schema`: (default: false) whether to implement `schemars::JsonSchema` describing what `serde` writes, so API servers can document their error payloads. Requires `serde`, the `schemars` feature and a dependency on `schemars` 1.x, and the kind and extra fields must implement `JsonSchema`
+/// - `utoipa`: (default: false) whether to implement `utoipa::ToSchema` describing what `serde` writes, so OpenAPI specs include the error body. Requires `serde`, the `utoipa` feature and a dependency on `utoipa` 5.x, and the kind and extra fields must implement `ToSchema`
+/// - `miette`: (default: false) whether to implement `miette::Diagnostic`, reporting the variant `code`, `help`, `docs_url` and `severity` attributes as the diagnostic code, help, url and severity. Requires the `miette` feature and a dependency on the `miette` crate
+/// - `defmt`: (default: false) whether to implement `defmt::Format`, writing the kind followed by `: ` and the source through `Display2Format`, or `Debug2Format` when `source_fn` is false, so the error can be logged with defmt. Only the first source is written in `sources` mode. Requires the `defmt` feature and a dependency on the `defmt` crate, and the kind must implement `defmt::Format`
+/// - `extra_fields`: (default: none) comma separated fields stored besides the kind and source, e.g. `"path: std::path::PathBuf, attempt: u32"`. `new()` takes them after the source in declaration order, each gets an accessor returning a reference (visibility from `kind_fn_vis`), and `display` can use them as placeholders like `{path}`. The other constructors and `From` impls fill them with `Default::default()`
+/// - `related`: (default: false) whether to keep secondary errors besides the source, e.g. the other failures of a batch. Generates `push_related()` (visibility from `new_vis`) taking anything convertible into `Box<dyn Error + Send + Sync>` and `related()` (visibility from `origin_fn_vis`). The `termination` report prints them after the source chain. Can't be combined with `io_clone`
+/// - `message`: (default: false) whether to store a human readable `Cow<'static, str>` message, set by the `new_with_message(kind, source, message)` constructor (visibility from `new_vis`), read by `message()` (visibility from `kind_fn_vis`) and usable as `{message}` in `display`. Other constructors leave it empty
+/// - `context`: (default: false) whether to keep key/value context pairs, attached with the builder style `with_context(key, value)` (visibility from `new_vis`) and listed by `context()` (visibility from `kind_fn_vis`). The `termination` report prints them below the error
+/// - `chain`: (default: false) whether to generate `chain()` and `root_cause()` (visibility from `origin_fn_vis`). `chain()` iterates over this error followed by each error along its source chain, and `root_cause()` returns the last one
+/// - `into_fns`: (default: false) whether to generate the consuming `into_kind()` (visibility from `kind_fn_vis`) and `into_source()` (visibility from `origin_fn_vis`), like `io::Error::into_inner()`, unboxing a boxed source
+/// - `map_fns`: (default: false) whether to generate the consuming `map_kind()` (visibility from `kind_fn_vis`), `map_source()` and `map_source_into()` (visibility from `origin_fn_vis`). `map_source()` takes and returns the source (the `Vec` of sources in `sources` mode), while `map_source_into()` maps the source into another error type implementing `From<(NewKind, NewSource)>`, e.g. a sibling error with `from_tuple` whose kind converts from this one, dropping the other fields
+/// - `into_parts`: (default: false) whether to generate the consuming `into_parts()` (visibility from `origin_fn_vis`), returning the kind and the source (the `Vec` of sources in `sources` mode), unboxing a boxed source
+/// - `get_ref`: (default: false) whether to generate `get_ref()` and `get_mut()` (visibility from `origin_fn_vis`) returning the source as a `dyn Error` like `io::Error` does. Requires `source_fn`
+/// - `kind_chain`: (default: false) whether to generate `kind_chain()` (visibility from `kind_fn_vis`), iterating over the kind of this error followed by the kinds of errors of the same type found along its source chain, i.e. every layer it was wrapped in
+/// - `display_detail`: (default: none) detailed format rendered by a generated `detail()` method returning a `String`, with the same placeholders as `display`. Only `display` is used by `Display`
+/// - `detail_fn_vis`: (default: pub) visibility of the `detail()` method
+/// - `repr`: (default: none) internal layout of the struct. `"compact"` behaves like `optional_source` but stores the source as `Option<Box<Source>>`, keeping the struct small, and implements `From<Kind>` without allocating, like `io::Error`. Can't be combined with `sources`, `const_new` or an already boxed source
+/// - `box_source`: (default: false) whether to store the source as `Box<Source>` to keep the struct small. `new()`, `origin()` and `replace_source()` keep taking and returning the unboxed source. Can't be combined with `sources`, `const_new` or an already boxed source
+/// - `arc_source`: (default: false) whether to store the source as `Arc<Source>`, so the error implements `Clone` even if the source doesn't, and can be handed to several tasks with `shared()`. `new()` and `origin()` keep taking and returning the unboxed source, while `replace_source()`, `into_parts()`, `into_source()` and `map_source()` work on the `Arc`. Requires the kind and `extra_fields` to implement `Clone`. Can't be combined with `sources`, `optional_source`, `box_source`, `const_new`, `io_clone`, per-variant sources or an already boxed source
+/// - `boxed`: (default: false) whether the struct is a newtype over `Box<{name}Inner>` holding the kind, source and other fields, so it is pointer sized no matter how large they are. All generated methods and impls read through the box. Can't be combined with `const_new`
+/// - `backtrace`: (default: false) whether to capture a `std::backtrace::Backtrace` when the error is created, exposed by `backtrace()`. `Error::provide()` is still unstable, so the backtrace isn't provided through it. Can't be combined with `const_new` or `io_clone`
+/// - `backtrace_policy`: (default: "always") when the backtrace is captured. `"always"` captures unconditionally, `"env"` follows `RUST_LIB_BACKTRACE` and `RUST_BACKTRACE` like `Backtrace::capture()`, and `"env:VAR"` captures only when the `VAR` environment variable is set to something other than `0`. Otherwise `backtrace()` returns a disabled backtrace
+/// - `backtrace_fn_vis`: (default: pub) visibility of the `backtrace()` and `span_trace()` methods
+/// - `cold_ctors`: (default: false) whether to mark the constructors and `From` impls `#[cold]` and `#[inline(never)]`, keeping error construction out of the hot path
+/// - `location`: (default: false) whether to record the caller location of the constructor with `#[track_caller]`, exposed by `location()` (visibility from `kind_fn_vis`) and appended to the default Display. Can't be combined with `const_new`
+/// - `span_trace`: (default: false) whether to capture a `tracing_error::SpanTrace` when the error is created, exposed by `span_trace()` and printed by the `termination` report. Requires the `tracing` feature and a dependency on the `tracing-error` crate. Can't be combined with `const_new`
+/// - `max_size`: (default: none) maximum size of the struct in bytes, e.g. `max_size = 24`, checked at compile time. Can't be combined with a generic kind enum
+/// - `from_tuple`: (default: false) whether to implement `From<(Kind, Source)>`, so `Err((kind, source).into())` builds the error. The source half is what `new()` takes, e.g. a `Vec` in `sources` mode
+/// - `catch_all`: (default: false) whether the source is `Box<dyn Error + Send + Sync>`, so any error can be wrapped without naming its type. Generates `boxed(kind, err)` and `wrap(kind, err)` constructors (visibility from `new_vis`) and `From<(Kind, E)>` for every `E: Error + Send + Sync + 'static`. Can't be combined with `source`
+/// - `optional_source`: (default: false) whether the source may be missing. The struct stores `Option<Source>`, `new(kind, source)` takes an `Option<Source>`, `from_kind(kind)` builds an error without a source, and `origin()` returns `Option<&Source>`. `{source}` in `display` renders a missing source as an empty string. Can't be combined with `sources`
+///
+/// # Variant attributes
+///
+/// - `source`: source type of the variant when it differs from the shared `source`, e.g. `#[kind_error(source = "ParseIntError")]`. The sources are then stored in a generated `{name}Source` enum, `new()` accepts anything convertible into it, and `origin()` returns `&(dyn Error + 'static)`. Every fieldless variant also gets a `new_<variant>(source)` constructor (visibility from `new_vis`, name in snake_case unless `rename_all` says otherwise) taking its own source type. Every source type must implement `Error`
+/// - `help`: help text of the variant, e.g. `#[kind_error(help = "check that the config file exists")]`. Generates a `help()` method (visibility from `kind_fn_vis`) returning `None` for variants without one, and the alternate Display (`{:#}`) ends with a `help: ...` line
+/// - `docs_url`: documentation URL of the variant, e.g. `#[kind_error(docs_url = "https://docs.example.com/errors/E102")]`. Generates a `docs_url()` method (visibility from `kind_fn_vis`) returning `None` for variants without one, and the `termination` report ends with a `See: ...` line
+/// - `from`: comma separated error types converted into this variant, e.g. `#[kind_error(from = "ParseIntError")]`. Generates `impl From<Type>` wrapping the error under the variant, converted into its source with `Into`. Only fieldless variants can use it
+/// - `severity`: severity reported by the `miette` Diagnostic impl, one of `"error"`, `"warning"` or `"advice"`. Variants without one leave it to the report handler, which treats them as errors
+/// - `code`: numeric code of the variant, e.g. `#[kind_error(code = 404)]`. When set on every variant, a `code()` method is generated, and for fieldless enums also a `from_code()` associated function mapping a code back to its kind. Both use `kind_fn_vis`
+/// - `alias`: old name of the variant constructor, e.g. `#[kind_error(alias = "missing")]` after renaming `Missing` to `NotFound`. Generates a `#[deprecated]` constructor with that name delegating to the current one, so callers keep compiling while they migrate. When the constructor would clash with another generated method, the first alias names it instead and isn't deprecated. Can be repeated, and must be a legal identifier not used by another generated method. Requires `variant_ctors`
+/// - `skip`: bare marker leaving an internal variant out of the per-variant methods, e.g. `#[kind_error(skip)]`. It gets no constructor, `is_`/`as_` method or `all_kinds()` entry and is never built by `arbitrary`, while `code()` reports `skip_code` for it and `help()`, `docs_url()` and the `miette` severity report `None`. `kind_name()` still names it. Can't be combined with the other variant attributes
 #[proc_macro_derive(KindError, attributes(kind_error))]
 pub fn kind_error(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
@@ -55,20 +171,152 @@ pub fn kind_error(input: TokenStream) -> TokenStream {
     kind_error_impl(input).unwrap_or_else(|err| err.to_compile_error().into())
 }
 
+/// Share `kind_error` attributes between every `KindError` enum in a module.
+///
+/// The defaults are merged into the `#[kind_error(...)]` attribute of each enum deriving
+/// `KindError` inside the module, including nested inline modules. Keys set on an enum itself
+/// override the shared defaults.
+///
+/// # Example
+///
+/// ```rust
+/// use kinderror::{KindError, kind_error_defaults};
+///
+/// #[kind_error_defaults(source = "std::io::Error", new_vis = "pub", type_vis = "pub")]
+/// mod errors {
+///     use kinderror::KindError;
+///
+///     #[derive(KindError, Debug, Eq, PartialEq)]
+///     #[kind_error(name = "ReadError")]
+///     pub enum ReadErrorKind {
+///         Eof,
+///     }
+///
+///     #[derive(KindError, Debug, Eq, PartialEq)]
+///     #[kind_error(name = "WriteError", new_vis = "pub(crate)")]
+///     pub enum WriteErrorKind {
+///         Full,
+///     }
+/// }
+///
+/// let err = errors::ReadError::new(errors::ReadErrorKind::Eof, std::io::Error::other("eof"));
+/// assert_eq!(*err.kind(), errors::ReadErrorKind::Eof);
+/// ```
+#[proc_macro_attribute]
+pub fn kind_error_defaults(args: TokenStream, input: TokenStream) -> TokenStream {
+    let defaults =
+        parse_macro_input!(args with Punctuated::<syn::MetaNameValue, Token![,]>::parse_terminated);
+    let mut module = parse_macro_input!(input as syn::ItemMod);
+
+    kind_error_defaults_impl(&defaults, &mut module)
+        .map(|()| quote! { #module }.into())
+        .unwrap_or_else(|err| err.to_compile_error().into())
+}
+
 struct KindErrorAttrs {
     source: Option<Type>,
     new_vis: Option<Visibility>,
-    name: Option<String>,
+    name: Option<syn::LitStr>,
     type_vis: Option<Visibility>,
     kind_fn_vis: Option<Visibility>,
     origin_fn_vis: Option<Visibility>,
     source_fn: bool,
     display: Option<String>,
+    debug: Option<syn::LitStr>,
+    debug_chain: bool,
+    source_lifetime: Option<Lifetime>,
+    termination: bool,
+    ord_by_kind: bool,
+    cmp_by_kind: bool,
+    error_bounds: Vec<WherePredicate>,
+    sources: bool,
+    code_ty: Option<Ident>,
+    from_code: Option<syn::LitBool>,
+    skip_code: Option<syn::LitInt>,
+    timestamp: bool,
+    timestamp_fn_vis: Option<Visibility>,
+    report_fn: Option<syn::Path>,
+    derive_debug: bool,
+    const_new: bool,
+    io_clone: bool,
+    validate_fn: Option<syn::Path>,
+    validate_try_fn: Option<syn::Path>,
+    validate_error: Option<Type>,
+    discriminant: bool,
+    default_kind: Option<syn::Expr>,
+    from_sources: Vec<Type>,
+    derives: Vec<syn::Path>,
+    struct_attrs: Vec<Attribute>,
+    non_exhaustive: bool,
+    must_use: bool,
+    inspect_fn: Option<syn::Path>,
+    all_kinds: bool,
+    kind_fn: bool,
+    replace_source_fn_vis: Option<Visibility>,
+    rename_all: Option<RenameRule>,
+    legacy_error_methods: bool,
+    repr_transparent: Option<syn::LitBool>,
+    source_display_fallback: bool,
+    arbitrary: bool,
+    serde: bool,
+    json_schema: bool,
+    utoipa: bool,
+    miette: bool,
+    defmt: bool,
+    display_detail: Option<String>,
+    detail_fn_vis: Option<Visibility>,
+    optional_source: bool,
+    catch_all: bool,
+    repr: Option<Repr>,
+    box_source: bool,
+    arc_source: bool,
+    boxed: bool,
+    max_size: Option<syn::LitInt>,
+    backtrace: bool,
+    backtrace_fn_vis: Option<Visibility>,
+    backtrace_policy: Option<(syn::LitStr, BacktracePolicy)>,
+    span_trace: bool,
+    location: bool,
+    extra_fields: Vec<(Ident, Type)>,
+    context: bool,
+    message: bool,
+    clock_fn: Option<syn::Path>,
+    related: bool,
+    kind_chain: bool,
+    get_ref: bool,
+    into_fns: bool,
+    map_fns: bool,
+    into_parts: bool,
+    chain: bool,
+    from_tuple: Option<syn::LitBool>,
+    source_default: Option<syn::Expr>,
+    variant_ctors: bool,
+    kind_into: bool,
+    builder: bool,
+    builder_name: Option<syn::LitStr>,
+    new_fn: bool,
+    classify_with: Option<syn::Path>,
+    const_fns: bool,
+    cold_ctors: bool,
+    mut_fn_vis: Option<Visibility>,
+    kind_by_value: bool,
+    as_fns: bool,
+    is_fns: bool,
+    kind_is_fns: bool,
+    eq_kind: bool,
+    delegate: Vec<DelegateFn>,
+    as_ref_error: bool,
+    kind_fn_name: Option<Ident>,
+    origin_fn_name: Option<Ident>,
+    new_fn_name: Option<Ident>,
+    fields_vis: Option<Visibility>,
+    kind_field: Option<Ident>,
+    source_field: Option<Ident>,
 }
 
-impl Parse for KindErrorAttrs {
-    fn parse(input: ParseStream) -> syn::Result<Self> {
-        let mut attrs = KindErrorAttrs {
+impl Default for KindErrorAttrs {
+    fn default() -> Self {
+        Self {
             source: None,
             new_vis: None,
             name: None,
@@ -77,7 +325,103 @@ impl Parse for KindErrorAttrs {
             origin_fn_vis: None,
             source_fn: true,
             display: None,
-        };
+            debug: None,
+            debug_chain: false,
+            source_lifetime: None,
+            termination: false,
+            ord_by_kind: false,
+            cmp_by_kind: false,
+            error_bounds: Vec::new(),
+            sources: false,
+            code_ty: None,
+            from_code: None,
+            skip_code: None,
+            timestamp: false,
+            timestamp_fn_vis: None,
+            report_fn: None,
+            derive_debug: true,
+            const_new: false,
+            io_clone: false,
+            validate_fn: None,
+            validate_try_fn: None,
+            validate_error: None,
+            discriminant: false,
+            default_kind: None,
+            from_sources: Vec::new(),
+            derives: Vec::new(),
+            struct_attrs: Vec::new(),
+            non_exhaustive: false,
+            must_use: false,
+            inspect_fn: None,
+            all_kinds: false,
+            kind_fn: true,
+            replace_source_fn_vis: None,
+            rename_all: None,
+            legacy_error_methods: false,
+            repr_transparent: None,
+            source_display_fallback: false,
+            arbitrary: false,
+            serde: false,
+            json_schema: false,
+            utoipa: false,
+            miette: false,
+            defmt: false,
+            display_detail: None,
+            detail_fn_vis: None,
+            optional_source: false,
+            catch_all: false,
+            repr: None,
+            box_source: false,
+            arc_source: false,
+            boxed: false,
+            max_size: None,
+            backtrace: false,
+            backtrace_fn_vis: None,
+            backtrace_policy: None,
+            span_trace: false,
+            location: false,
+            extra_fields: Vec::new(),
+            context: false,
+            message: false,
+            clock_fn: None,
+            related: false,
+            kind_chain: false,
+            get_ref: false,
+            into_fns: false,
+            map_fns: false,
+            into_parts: false,
+            chain: false,
+            from_tuple: None,
+            source_default: None,
+            variant_ctors: true,
+            kind_into: false,
+            builder: false,
+            builder_name: None,
+            new_fn: true,
+            classify_with: None,
+            const_fns: false,
+            cold_ctors: false,
+            mut_fn_vis: None,
+            kind_by_value: false,
+            as_fns: false,
+            is_fns: false,
+            kind_is_fns: false,
+            eq_kind: false,
+            delegate: Vec::new(),
+            as_ref_error: false,
+            kind_fn_name: None,
+            origin_fn_name: None,
+            new_fn_name: None,
+            fields_vis: None,
+            kind_field: None,
+            source_field: None,
+        }
+    }
+}
+
+impl Parse for KindErrorAttrs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut attrs = KindErrorAttrs::default();
 
         while !input.is_empty() {
             let key = input.parse::<Ident>()?;
@@ -86,7 +430,7 @@ impl Parse for KindErrorAttrs {
             match key.to_string().as_str() {
                 "source" => {
                     let lit_str = input.parse::<syn::LitStr>()?;
-                    attrs.source = Some(syn::parse_str::<Type>(&lit_str.value())?);
+                    attrs.source = Some(lit_str.parse::<Type>()?);
                 }
                 "new_vis" => {
                     let lit_str = input.parse::<syn::LitStr>()?;
@@ -94,7 +438,7 @@ impl Parse for KindErrorAttrs {
                 }
                 "name" => {
                     let lit_str = input.parse::<syn::LitStr>()?;
-                    attrs.name = Some(lit_str.value());
+                    attrs.name = Some(lit_str);
                 }
                 "type_vis" => {
                     let lit_str = input.parse::<syn::LitStr>()?;
@@ -116,6 +460,420 @@ impl Parse for KindErrorAttrs {
                     let lit_str = input.parse::<syn::LitStr>()?;
                     attrs.display = Some(lit_str.value());
                 }
+                "debug" => {
+                    attrs.debug = Some(input.parse::<syn::LitStr>()?);
+                }
+                "debug_chain" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.debug_chain = lit_bool.value();
+                }
+                "source_lifetime" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.source_lifetime = Some(lit_str.parse::<Lifetime>()?);
+                }
+                "termination" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.termination = lit_bool.value();
+                }
+                "cmp_by_kind" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.cmp_by_kind = lit_bool.value();
+                }
+                "ord_by_kind" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.ord_by_kind = lit_bool.value();
+                }
+                "error_bounds" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    let predicates = lit_str
+                        .parse_with(Punctuated::<WherePredicate, Token![,]>::parse_terminated)?;
+                    attrs.error_bounds = predicates.into_iter().collect();
+                }
+                "sources" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.sources = lit_bool.value();
+                }
+                "code_ty" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.code_ty = Some(lit_str.parse::<Ident>()?);
+                }
+                "from_code" => {
+                    attrs.from_code = Some(input.parse::<syn::LitBool>()?);
+                }
+                "skip_code" => {
+                    attrs.skip_code = Some(input.parse::<syn::LitInt>()?);
+                }
+                "timestamp" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.timestamp = lit_bool.value();
+                }
+                "timestamp_fn_vis" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.timestamp_fn_vis = Some(syn::parse_str::<Visibility>(&lit_str.value())?);
+                }
+                "report_fn" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.report_fn = Some(lit_str.parse::<syn::Path>()?);
+                }
+                "derive_debug" | "auto_debug" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.derive_debug = lit_bool.value();
+                }
+                "const_new" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.const_new = lit_bool.value();
+                }
+                "io_clone" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.io_clone = lit_bool.value();
+                }
+                "validate_fn" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.validate_fn = Some(lit_str.parse::<syn::Path>()?);
+                }
+                "validate_try_fn" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.validate_try_fn = Some(lit_str.parse::<syn::Path>()?);
+                }
+                "validate_error" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.validate_error = Some(syn::parse_str::<Type>(&lit_str.value())?);
+                }
+                "discriminant" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.discriminant = lit_bool.value();
+                }
+                "default_kind" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.default_kind = Some(lit_str.parse::<syn::Expr>()?);
+                }
+                "cold_ctors" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.cold_ctors = lit_bool.value();
+                }
+                "const_fns" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.const_fns = lit_bool.value();
+                }
+                "classify_with" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.classify_with = Some(lit_str.parse::<syn::Path>()?);
+                }
+                "new_fn" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.new_fn = lit_bool.value();
+                }
+                "builder" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.builder = lit_bool.value();
+                }
+                "builder_name" => {
+                    attrs.builder_name = Some(input.parse::<syn::LitStr>()?);
+                }
+                "kind_into" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.kind_into = lit_bool.value();
+                }
+                "variant_ctors" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.variant_ctors = lit_bool.value();
+                }
+                "source_default" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.source_default = Some(lit_str.parse::<syn::Expr>()?);
+                }
+                "from_sources" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    let types =
+                        lit_str.parse_with(Punctuated::<Type, Token![,]>::parse_terminated)?;
+                    attrs.from_sources = types.into_iter().collect();
+                }
+                "derive" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    let paths =
+                        lit_str.parse_with(Punctuated::<syn::Path, Token![,]>::parse_terminated)?;
+                    attrs.derives = paths.into_iter().collect();
+                }
+                "struct_attrs" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.struct_attrs = lit_str.parse_with(Attribute::parse_outer)?;
+                }
+                "non_exhaustive" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.non_exhaustive = lit_bool.value();
+                }
+                "must_use" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.must_use = lit_bool.value();
+                }
+                "inspect_fn" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.inspect_fn = Some(lit_str.parse::<syn::Path>()?);
+                }
+                "all_kinds" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.all_kinds = lit_bool.value();
+                }
+                "kind_fn" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.kind_fn = lit_bool.value();
+                }
+                "fields_vis" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.fields_vis = Some(syn::parse_str::<Visibility>(&lit_str.value())?);
+                }
+                "kind_field" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.kind_field = Some(lit_str.parse::<Ident>()?);
+                }
+                "source_field" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.source_field = Some(lit_str.parse::<Ident>()?);
+                }
+                "kind_fn_name" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.kind_fn_name = Some(lit_str.parse::<Ident>()?);
+                }
+                "origin_fn_name" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.origin_fn_name = Some(lit_str.parse::<Ident>()?);
+                }
+                "new_fn_name" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.new_fn_name = Some(lit_str.parse::<Ident>()?);
+                }
+                "as_ref_error" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.as_ref_error = lit_bool.value();
+                }
+                "delegate" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    let delegate = lit_str
+                        .parse_with(Punctuated::<DelegateFn, Token![;]>::parse_terminated)?;
+                    attrs.delegate.extend(delegate);
+                }
+                "eq_kind" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.eq_kind = lit_bool.value();
+                }
+                "is_fns" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.is_fns = lit_bool.value();
+                }
+                "kind_is_fns" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.kind_is_fns = lit_bool.value();
+                }
+                "as_fns" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.as_fns = lit_bool.value();
+                }
+                "kind_by_value" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.kind_by_value = lit_bool.value();
+                }
+                "mut_fn_vis" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.mut_fn_vis = Some(syn::parse_str::<Visibility>(&lit_str.value())?);
+                }
+                "replace_source_fn_vis" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.replace_source_fn_vis =
+                        Some(syn::parse_str::<Visibility>(&lit_str.value())?);
+                }
+                "rename_all" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.rename_all = Some(RenameRule::from_lit(&lit_str)?);
+                }
+                "legacy_error_methods" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.legacy_error_methods = lit_bool.value();
+                }
+                "repr_transparent" => {
+                    attrs.repr_transparent = Some(input.parse::<syn::LitBool>()?);
+                }
+                "source_display_fallback" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.source_display_fallback = lit_bool.value();
+                }
+                "arbitrary" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    if lit_bool.value() && !cfg!(feature = "arbitrary") {
+                        return Err(syn::Error::new_spanned(
+                            &lit_bool,
+                            "the arbitrary attribute requires the `arbitrary` feature of kinderror",
+                        ));
+                    }
+                    attrs.arbitrary = lit_bool.value();
+                }
+                "serde" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    if lit_bool.value() && !cfg!(feature = "serde") {
+                        return Err(syn::Error::new_spanned(
+                            &lit_bool,
+                            "the serde attribute requires the `serde` feature of kinderror",
+                        ));
+                    }
+                    attrs.serde = lit_bool.value();
+                }
+                "json_schema" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    if lit_bool.value() && !cfg!(feature = "schemars") {
+                        return Err(syn::Error::new_spanned(
+                            &lit_bool,
+                            "the json_schema attribute requires the `schemars` feature of kinderror",
+                        ));
+                    }
+                    attrs.json_schema = lit_bool.value();
+                }
+                "utoipa" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    if lit_bool.value() && !cfg!(feature = "utoipa") {
+                        return Err(syn::Error::new_spanned(
+                            &lit_bool,
+                            "the utoipa attribute requires the `utoipa` feature of kinderror",
+                        ));
+                    }
+                    attrs.utoipa = lit_bool.value();
+                }
+                "miette" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    if lit_bool.value() && !cfg!(feature = "miette") {
+                        return Err(syn::Error::new_spanned(
+                            &lit_bool,
+                            "the miette attribute requires the `miette` feature of kinderror",
+                        ));
+                    }
+                    attrs.miette = lit_bool.value();
+                }
+                "defmt" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    if lit_bool.value() && !cfg!(feature = "defmt") {
+                        return Err(syn::Error::new_spanned(
+                            &lit_bool,
+                            "the defmt attribute requires the `defmt` feature of kinderror",
+                        ));
+                    }
+                    attrs.defmt = lit_bool.value();
+                }
+                "span_trace" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    if lit_bool.value() && !cfg!(feature = "tracing") {
+                        return Err(syn::Error::new_spanned(
+                            &lit_bool,
+                            "the span_trace attribute requires the `tracing` feature of kinderror",
+                        ));
+                    }
+                    attrs.span_trace = lit_bool.value();
+                }
+                "location" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.location = lit_bool.value();
+                }
+                "extra_fields" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    let fields = lit_str.parse_with(|input: ParseStream| {
+                        Punctuated::<syn::Field, Token![,]>::parse_terminated_with(
+                            input,
+                            syn::Field::parse_named,
+                        )
+                    })?;
+                    attrs.extra_fields = fields
+                        .into_iter()
+                        .map(|field| (field.ident.expect("named field"), field.ty))
+                        .collect();
+                }
+                "context" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.context = lit_bool.value();
+                }
+                "message" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.message = lit_bool.value();
+                }
+                "clock_fn" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.clock_fn = Some(lit_str.parse::<syn::Path>()?);
+                }
+                "related" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.related = lit_bool.value();
+                }
+                "chain" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.chain = lit_bool.value();
+                }
+                "kind_chain" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.kind_chain = lit_bool.value();
+                }
+                "get_ref" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.get_ref = lit_bool.value();
+                }
+                "into_fns" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.into_fns = lit_bool.value();
+                }
+                "map_fns" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.map_fns = lit_bool.value();
+                }
+                "into_parts" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.into_parts = lit_bool.value();
+                }
+                "from_tuple" => {
+                    attrs.from_tuple = Some(input.parse::<syn::LitBool>()?);
+                }
+                "display_detail" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.display_detail = Some(lit_str.value());
+                }
+                "detail_fn_vis" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.detail_fn_vis = Some(syn::parse_str::<Visibility>(&lit_str.value())?);
+                }
+                "optional_source" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.optional_source = lit_bool.value();
+                }
+                "catch_all" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.catch_all = lit_bool.value();
+                }
+                "repr" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.repr = Some(Repr::from_lit(&lit_str)?);
+                }
+                "box_source" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.box_source = lit_bool.value();
+                }
+                "arc_source" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.arc_source = lit_bool.value();
+                }
+                "boxed" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.boxed = lit_bool.value();
+                }
+                "max_size" => {
+                    attrs.max_size = Some(input.parse::<syn::LitInt>()?);
+                }
+                "backtrace" => {
+                    let lit_bool = input.parse::<syn::LitBool>()?;
+                    attrs.backtrace = lit_bool.value();
+                }
+                "backtrace_fn_vis" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.backtrace_fn_vis = Some(syn::parse_str::<Visibility>(&lit_str.value())?);
+                }
+                "backtrace_policy" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    let policy = BacktracePolicy::from_lit(&lit_str)?;
+                    attrs.backtrace_policy = Some((lit_str, policy));
+                }
                 _ => {
                     return Err(syn::Error::new_spanned(
                         &key,
@@ -134,13 +892,112 @@ impl Parse for KindErrorAttrs {
     }
 }
 
-fn parse_kind_error_attrs(attrs: &[Attribute]) -> syn::Result<KindErrorAttrs> {
+/// Attributes placed on a single variant of the kind enum.
+#[derive(Default)]
+struct VariantAttrs {
+    code: Option<syn::LitInt>,
+    source: Option<Type>,
+    help: Option<syn::LitStr>,
+    docs_url: Option<syn::LitStr>,
+    severity: Option<(syn::LitStr, Ident)>,
+    from: Vec<Type>,
+    skip: Option<Ident>,
+    alias: Vec<(syn::LitStr, Ident)>,
+}
+
+impl Parse for VariantAttrs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut attrs = VariantAttrs::default();
+
+        while !input.is_empty() {
+            let key = input.parse::<Ident>()?;
+            // `skip` is a bare marker, every other key takes a value
+            if key == "skip" {
+                if input.peek(Token![=]) {
+                    return Err(syn::Error::new_spanned(
+                        &key,
+                        "skip takes no value, e.g. #[kind_error(skip)]",
+                    ));
+                }
+                attrs.skip = Some(key);
+                if !input.is_empty() {
+                    input.parse::<Token![,]>()?;
+                }
+                continue;
+            }
+            input.parse::<Token![=]>()?;
+
+            match key.to_string().as_str() {
+                "code" => {
+                    attrs.code = Some(input.parse::<syn::LitInt>()?);
+                }
+                "source" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    attrs.source = Some(lit_str.parse::<Type>()?);
+                }
+                "help" => {
+                    attrs.help = Some(input.parse::<syn::LitStr>()?);
+                }
+                "docs_url" => {
+                    attrs.docs_url = Some(input.parse::<syn::LitStr>()?);
+                }
+                "severity" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    let severity = match lit_str.value().as_str() {
+                        "error" => "Error",
+                        "warning" => "Warning",
+                        "advice" => "Advice",
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                &lit_str,
+                                "severity must be one of \"error\", \"warning\" or \"advice\"",
+                            ));
+                        }
+                    };
+                    let severity = Ident::new(severity, lit_str.span());
+                    attrs.severity = Some((lit_str, severity));
+                }
+                "from" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    let types =
+                        lit_str.parse_with(Punctuated::<Type, Token![,]>::parse_terminated)?;
+                    attrs.from.extend(types);
+                }
+                "alias" => {
+                    let lit_str = input.parse::<syn::LitStr>()?;
+                    let alias = lit_str.parse::<Ident>().map_err(|_| {
+                        syn::Error::new_spanned(
+                            &lit_str,
+                            "alias must be a legal identifier, e.g. \"old_name\"",
+                        )
+                    })?;
+                    attrs.alias.push((lit_str, alias));
+                }
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        &key,
+                        format!("unknown variant attribute key: {}", key),
+                    ));
+                }
+            }
+
+            // Handle comma separation
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(attrs)
+    }
+}
+
+fn parse_kind_error_attrs<T: Parse + Default>(attrs: &[Attribute]) -> syn::Result<T> {
     let kind_error_attr = attrs.iter().find(|attr| attr.path().is_ident("kind_error"));
 
     if let Some(attr) = kind_error_attr {
         match &attr.meta {
             Meta::List(meta_list) => {
-                let attrs: KindErrorAttrs = meta_list.parse_args()?;
+                let attrs: T = meta_list.parse_args()?;
                 Ok(attrs)
             }
             _ => Err(syn::Error::new_spanned(
@@ -150,22 +1007,13 @@ fn parse_kind_error_attrs(attrs: &[Attribute]) -> syn::Result<KindErrorAttrs> {
         }
     } else {
         // Return empty attributes, default values will be used
-        Ok(KindErrorAttrs {
-            source: None,
-            new_vis: None,
-            name: None,
-            type_vis: None,
-            kind_fn_vis: None,
-            origin_fn_vis: None,
-            source_fn: true,
-            display: None,
-        })
+        Ok(T::default())
     }
 }
 
 fn kind_error_impl(input: DeriveInput) -> Result<TokenStream, syn::Error> {
-    match &input.data {
-        Data::Enum(_) => {}
+    let data = match &input.data {
+        Data::Enum(data) => data,
         _ => {
             return Err(syn::Error::new_spanned(
                 &input,
@@ -174,27 +1022,579 @@ fn kind_error_impl(input: DeriveInput) -> Result<TokenStream, syn::Error> {
         }
     };
 
-    let attrs = parse_kind_error_attrs(&input.attrs)?;
-
-    let source_type = attrs
-        .source
-        .ok_or_else(|| syn::Error::new_spanned(&input, "source attribute is required"))?;
-    let kind_type = &input.ident;
-    let new_vis = attrs.new_vis.unwrap_or(Visibility::Inherited);
-    let type_vis = attrs.type_vis.unwrap_or(Visibility::Inherited);
-    let kind_fn_vis = attrs
-        .kind_fn_vis
+    let mut attrs: KindErrorAttrs = parse_kind_error_attrs(&input.attrs)?;
+    attrs.const_new |= attrs.const_fns;
+    let accessor_constness = if attrs.const_fns {
+        quote! { const }
+    } else {
+        quote! {}
+    };
+    let variants = data
+        .variants
+        .iter()
+        .map(|variant| {
+            Ok((
+                variant,
+                parse_kind_error_attrs::<VariantAttrs>(&variant.attrs)?,
+            ))
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    // A skipped variant is left out of the per-variant methods, so it can't carry what they report
+    for (_, variant_attrs) in &variants {
+        let Some(skip) = &variant_attrs.skip else {
+            continue;
+        };
+        let conflict = if variant_attrs.code.is_some() {
+            Some("code")
+        } else if variant_attrs.help.is_some() {
+            Some("help")
+        } else if variant_attrs.docs_url.is_some() {
+            Some("docs_url")
+        } else if variant_attrs.severity.is_some() {
+            Some("severity")
+        } else if !variant_attrs.from.is_empty() {
+            Some("from")
+        } else if variant_attrs.source.is_some() {
+            Some("source")
+        } else if !variant_attrs.alias.is_empty() {
+            Some("alias")
+        } else {
+            None
+        };
+        if let Some(conflict) = conflict {
+            return Err(syn::Error::new_spanned(
+                skip,
+                format!("skip can't be combined with {}", conflict),
+            ));
+        }
+    }
+
+    // Without a source the struct only wraps the kind
+    let source_type = match (&attrs.source, attrs.catch_all) {
+        (Some(source), true) => {
+            return Err(syn::Error::new_spanned(
+                source,
+                "catch_all already sets the source to Box<dyn Error + Send + Sync>",
+            ));
+        }
+        (None, true) => Some(parse_quote! {
+            ::std::boxed::Box<dyn ::core::error::Error + ::core::marker::Send + ::core::marker::Sync>
+        }),
+        (source, false) => source.clone(),
+    };
+    let kind_ident = &input.ident;
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let kind_type = quote! { #kind_ident #ty_generics };
+    let new_vis = attrs.new_vis.clone().unwrap_or(Visibility::Inherited);
+    // Names and visibility of the struct fields holding the kind and the source
+    let fields_vis = attrs.fields_vis.clone().unwrap_or(Visibility::Inherited);
+    let kind_field_name = attrs
+        .kind_field
+        .clone()
+        .unwrap_or_else(|| format_ident!("kind"));
+    let source_field_name = attrs.source_field.clone().unwrap_or_else(|| {
+        if attrs.sources {
+            format_ident!("sources")
+        } else {
+            format_ident!("source")
+        }
+    });
+    let kind_fn_name = attrs
+        .kind_fn_name
+        .clone()
+        .unwrap_or_else(|| format_ident!("kind"));
+    let origin_fn_name = attrs
+        .origin_fn_name
+        .clone()
+        .unwrap_or_else(|| format_ident!("origin"));
+    // Without new_fn the constructor the others are built on stays private under a hidden name
+    let (new_ident, new_fn_vis, new_fn_allow) = if attrs.new_fn {
+        (
+            attrs
+                .new_fn_name
+                .clone()
+                .unwrap_or_else(|| format_ident!("new")),
+            new_vis.clone(),
+            quote! {},
+        )
+    } else {
+        (
+            format_ident!("__new"),
+            Visibility::Inherited,
+            quote! { #[allow(dead_code)] },
+        )
+    };
+    let type_vis = attrs.type_vis.clone().unwrap_or(Visibility::Inherited);
+    let kind_fn_vis = attrs
+        .kind_fn_vis
+        .clone()
         .unwrap_or(Visibility::Public(Default::default()));
     let origin_fn_vis = attrs
         .origin_fn_vis
+        .clone()
         .unwrap_or(Visibility::Public(Default::default()));
-    let name_str = attrs.name.as_deref().unwrap_or("Error");
-    let name = Ident::new(name_str, input.ident.span());
+    let name_str = attrs
+        .name
+        .as_ref()
+        .map_or_else(|| "Error".to_string(), syn::LitStr::value);
+    let name = Ident::new(&name_str, input.ident.span());
+    if name == input.ident {
+        let message = format!(
+            "the generated struct name must differ from the kind enum {}",
+            input.ident
+        );
+        return Err(match &attrs.name {
+            Some(lit_str) => syn::Error::new_spanned(lit_str, message),
+            None => syn::Error::new_spanned(&input.ident, message),
+        });
+    }
+
+    if let Some(lifetime) = &attrs.source_lifetime
+        && !generics
+            .lifetimes()
+            .any(|param| param.lifetime == *lifetime)
+    {
+        return Err(syn::Error::new_spanned(
+            lifetime,
+            format!("lifetime {} is not declared on {}", lifetime, kind_ident),
+        ));
+    }
+
+    // Variants whose source differs from the shared one are stored in a generated source enum,
+    // while the common case keeps the plain source field
+    // A compact repr makes the source optional and boxes it, so a kind-only error doesn't allocate
+    let compact = matches!(attrs.repr, Some(Repr::Compact));
+    let optional_source = attrs.optional_source || compact;
+    let box_source = attrs.box_source || compact;
+
+    if source_type.is_none() {
+        if let Some(variant_source) = variants
+            .iter()
+            .find_map(|(_, variant_attrs)| variant_attrs.source.as_ref())
+        {
+            return Err(syn::Error::new_spanned(
+                variant_source,
+                "per-variant source types require the source attribute",
+            ));
+        }
+
+        let conflict = if attrs.sources {
+            Some("sources")
+        } else if attrs.optional_source {
+            Some("optional_source")
+        } else if compact {
+            Some("repr = \"compact\"")
+        } else if attrs.box_source {
+            Some("box_source")
+        } else if attrs.arc_source {
+            Some("arc_source")
+        } else if attrs.source_lifetime.is_some() {
+            Some("source_lifetime")
+        } else if attrs.source_display_fallback {
+            Some("source_display_fallback")
+        } else if attrs.io_clone {
+            Some("io_clone")
+        } else if !attrs.from_sources.is_empty() {
+            Some("from_sources")
+        } else if attrs.default_kind.is_some() {
+            Some("default_kind")
+        } else if attrs.source_default.is_some() {
+            Some("source_default")
+        } else {
+            None
+        };
+        if let Some(conflict) = conflict {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                format!("{} requires the source attribute", conflict),
+            ));
+        }
+    }
+
+    let source_enum = source_type
+        .as_ref()
+        .and_then(|source_type| variant_source_enum(&variants, source_type, &name, &type_vis));
+    if let Some(source_enum) = &source_enum {
+        let conflict = if !attrs.source_fn {
+            Some("source_fn = false")
+        } else if attrs.catch_all {
+            Some("catch_all")
+        } else if attrs.sources {
+            Some("sources")
+        } else if attrs.optional_source {
+            Some("optional_source")
+        } else if compact {
+            Some("repr = \"compact\"")
+        } else if attrs.box_source {
+            Some("box_source")
+        } else if attrs.arc_source {
+            Some("arc_source")
+        } else if attrs.const_new {
+            Some("const_new")
+        } else if attrs.io_clone {
+            Some("io_clone")
+        } else if !generics.params.is_empty() {
+            Some("a generic kind enum")
+        } else {
+            None
+        };
+        if let Some(conflict) = conflict {
+            return Err(syn::Error::new_spanned(
+                &source_enum.first_variant_source,
+                format!(
+                    "per-variant source types can't be combined with {}",
+                    conflict
+                ),
+            ));
+        }
+    }
+    if let (true, Some(source_default)) = (optional_source, &attrs.source_default) {
+        return Err(syn::Error::new_spanned(
+            source_default,
+            "source_default can't be combined with an optional source, which already builds errors without one",
+        ));
+    }
+    if optional_source && attrs.sources {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            format!(
+                "{} can't be combined with sources, as an empty Vec already means no source",
+                if compact {
+                    "repr = \"compact\""
+                } else {
+                    "optional_source"
+                }
+            ),
+        ));
+    }
+    let stored_source_type = match &source_enum {
+        Some(source_enum) => {
+            let ident = &source_enum.ident;
+            quote! { #ident }
+        }
+        None if optional_source => quote! { ::core::option::Option<#source_type> },
+        None => quote! { #source_type },
+    };
+
+    // `Box<dyn Error>` doesn't implement Error itself, so a boxed trait object source reports the
+    // boxed error directly
+    let boxed_dyn_source = source_type.as_ref().is_some_and(is_boxed_trait_object);
+
+    if box_source {
+        let boxed_by = if compact {
+            "repr = \"compact\""
+        } else {
+            "box_source"
+        };
+        if boxed_dyn_source {
+            return Err(syn::Error::new_spanned(
+                &source_type,
+                format!("{} would box a source that is already boxed", boxed_by),
+            ));
+        }
+        if attrs.const_new {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                format!(
+                    "const_new can't be combined with {}, as boxing the source allocates",
+                    boxed_by
+                ),
+            ));
+        }
+        if attrs.sources {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                format!("{} can't be combined with sources", boxed_by),
+            ));
+        }
+    }
+    // A shared source is reference counted, so cloning the error never clones the source
+    let arc_source = attrs.arc_source;
+    if arc_source {
+        let conflict = if attrs.sources {
+            Some("sources")
+        } else if optional_source {
+            Some("an optional source")
+        } else if box_source {
+            Some("box_source")
+        } else if attrs.const_new {
+            Some("const_new")
+        } else if attrs.io_clone {
+            Some("io_clone")
+        } else if boxed_dyn_source {
+            Some("an already boxed source, use an Arc<dyn Error> source instead")
+        } else {
+            None
+        };
+        if let Some(conflict) = conflict {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                format!("arc_source can't be combined with {}", conflict),
+            ));
+        }
+    }
+    // How a source is stored in and read back from the struct field
+    let (source_field_type, store_source): (_, fn(proc_macro2::TokenStream) -> _) =
+        match (optional_source, box_source) {
+            _ if arc_source => (
+                quote! { ::std::sync::Arc<#source_type> },
+                |source| quote! { ::std::sync::Arc::new(#source) },
+            ),
+            (true, true) => (
+                quote! { ::core::option::Option<::std::boxed::Box<#source_type>> },
+                |source| quote! { #source.map(::std::boxed::Box::new) },
+            ),
+            (true, false) => (quote! { ::core::option::Option<#source_type> }, |source| {
+                source
+            }),
+            (false, true) => (
+                quote! { ::std::boxed::Box<#source_type> },
+                |source| quote! { ::std::boxed::Box::new(#source) },
+            ),
+            (false, false) => (quote! { #source_type }, |source| source),
+        };
 
-    let source_method = if attrs.source_fn {
+    // With `boxed` the fields live in an inner struct behind the struct's only field
+    let inner_name = format_ident!("{}Inner", name);
+    let (this, other, error) = if attrs.boxed {
+        (quote! { self.0 }, quote! { other.0 }, quote! { error.0 })
+    } else {
+        (quote! { self }, quote! { other }, quote! { error })
+    };
+    let this_kind = quote! { #this.#kind_field_name };
+    let construct = |fields: proc_macro2::TokenStream| {
+        if attrs.boxed {
+            quote! { Self(::std::boxed::Box::new(#inner_name { #fields })) }
+        } else {
+            quote! { Self { #fields } }
+        }
+    };
+    // Constructors forward their caller, so the recorded location is where the error was created,
+    // and may be kept out of the hot path
+    let track_caller = if attrs.location {
+        quote! { #[track_caller] }
+    } else {
+        quote! {}
+    };
+    let ctor_attrs = if attrs.cold_ctors {
+        quote! { #track_caller #[cold] #[inline(never)] }
+    } else {
+        track_caller
+    };
+    // Functions returning the struct are covered by the attribute on the struct itself
+    let must_use = if attrs.must_use {
+        quote! { #[must_use] }
+    } else {
+        quote! {}
+    };
+    if attrs.boxed && attrs.const_new {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "const_new can't be combined with boxed, as boxing the error allocates",
+        ));
+    }
+
+    // In sources mode all causes are kept, and the first one is reported as the source
+    let first_source = if attrs.sources && boxed_dyn_source {
+        quote! { #this.#source_field_name.first().map(|source| &**source) }
+    } else if attrs.sources {
+        quote! { #this.#source_field_name.first() }
+    } else if optional_source && (boxed_dyn_source || box_source) {
+        quote! { #this.#source_field_name.as_deref() }
+    } else if optional_source {
+        quote! { #this.#source_field_name.as_ref() }
+    } else if boxed_dyn_source || box_source || arc_source {
+        quote! { Some(&*#this.#source_field_name) }
+    } else if source_enum.is_some() {
+        quote! { Some(#this.#source_field_name.as_error()) }
+    } else {
+        quote! { Some(&#this.#source_field_name) }
+    };
+
+    // `Error::source()` requires a `'static` trait object, so a borrowed source is exposed
+    // through an inherent `source()` method instead
+    let source_fn = attrs.source_fn && source_type.is_some();
+    let (source_method, source_lifetime_method) = match (source_fn, &attrs.source_lifetime) {
+        (false, _) => (quote! {}, quote! {}),
+        (true, None) => (
+            quote! {
+                fn source(&self) -> Option<&(dyn ::core::error::Error + 'static)> {
+                    #first_source.map(|source| source as &(dyn ::core::error::Error + 'static))
+                }
+            },
+            quote! {},
+        ),
+        (true, Some(_)) => (
+            quote! {},
+            quote! {
+                #origin_fn_vis fn source(&self) -> Option<&(dyn ::core::error::Error + '_)> {
+                    #first_source.map(|source| source as &(dyn ::core::error::Error + '_))
+                }
+            },
+        ),
+    };
+
+    // A source that is one of the enum's type parameters needs explicit bounds, as the
+    // Display and Error impls can't assume anything about it
+    let generic_source = match &source_type {
+        Some(Type::Path(type_path)) if type_path.qself.is_none() => type_path
+            .path
+            .get_ident()
+            .filter(|ident| generics.type_params().any(|param| param.ident == **ident)),
+        _ => None,
+    };
+    let source_bound: Option<WherePredicate> = generic_source.map(|ident| {
+        if attrs.source_fn {
+            parse_quote!(#ident: ::core::error::Error + 'static)
+        } else {
+            parse_quote!(#ident: ::core::fmt::Debug)
+        }
+    });
+
+    // Check the source up front, so a source that isn't an Error points at `source_fn`
+    // instead of failing deep inside the generated `Error::source()`
+    let source_assertion = if source_fn
+        && attrs.source_lifetime.is_none()
+        && generics.params.is_empty()
+        && !boxed_dyn_source
+    {
+        let source_types = source_type.iter().chain(
+            source_enum
+                .iter()
+                .flat_map(|source_enum| source_enum.variants.iter().map(|(_, ty)| *ty)),
+        );
+
+        quote! {
+            const _: () = {
+                #[diagnostic::on_unimplemented(
+                    message = "the source `{Self}` does not implement `Error + 'static`",
+                    label = "source type used by kind_error",
+                    note = "set `source_fn = false` if the source doesn't implement Error"
+                )]
+                trait SourceIsError {}
+
+                impl<T: ::core::error::Error + 'static> SourceIsError for T {}
+
+                fn assert_source_is_error<T: SourceIsError>() {}
+
+                fn assert_source() {
+                    #(assert_source_is_error::<#source_types>();)*
+                }
+            };
+        }
+    } else {
+        quote! {}
+    };
+
+    let display_generics = {
+        let mut generics = generics.clone();
+        generics
+            .make_where_clause()
+            .predicates
+            .extend(source_bound.clone());
+        generics
+    };
+    let display_where_clause = &display_generics.where_clause;
+    let error_generics = {
+        let mut generics = generics.clone();
+        generics.make_where_clause().predicates.extend(
+            source_bound
+                .iter()
+                .cloned()
+                .chain(attrs.error_bounds.iter().cloned()),
+        );
+        generics
+    };
+    let error_where_clause = &error_generics.where_clause;
+
+    // Placeholder bindings shared by the `display` and `display_detail` templates
+    let field_idents = attrs.extra_fields.iter().map(|(ident, _)| ident);
+    let message_binding = attrs
+        .message
+        .then(|| quote! { let message = &#this.message; });
+    let kind_binding = quote! {
+        let kind = &#this.#kind_field_name;
+        #message_binding
+        #(let #field_idents = &#this.#field_idents;)*
+    };
+    // In sources mode `{source}` renders the number of sources, as there may be none
+    let source_binding = if source_type.is_none() {
+        quote! {}
+    } else if attrs.sources {
+        quote! { let source = #this.#source_field_name.len(); }
+    } else if optional_source {
+        // A missing source renders as an empty string, and `{source:?}` as the Option itself
+        let display_source = if attrs.source_display_fallback {
+            quote! { ::core::fmt::Debug::fmt(source, f) }
+        } else {
+            quote! { ::core::fmt::Display::fmt(source, f) }
+        };
+        let display_bound = if attrs.source_display_fallback {
+            quote! { ::core::fmt::Debug }
+        } else {
+            quote! { ::core::fmt::Display }
+        };
+
+        quote! {
+            struct OptionalSource<'a, T>(&'a ::core::option::Option<T>);
+
+            impl<T: #display_bound> ::core::fmt::Display for OptionalSource<'_, T> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    match self.0 {
+                        Some(source) => #display_source,
+                        None => Ok(()),
+                    }
+                }
+            }
+
+            impl<T: ::core::fmt::Debug> ::core::fmt::Debug for OptionalSource<'_, T> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    ::core::fmt::Debug::fmt(self.0, f)
+                }
+            }
+
+            let source = OptionalSource(&#this.#source_field_name);
+        }
+    } else if attrs.source_display_fallback {
+        // Render the source with Debug no matter which format trait the template uses
         quote! {
-            fn source(&self) -> Option<&(dyn ::core::error::Error + 'static)> {
-                Some(&self.source)
+            struct DebugAsDisplay<'a, T: ?Sized>(&'a T);
+
+            impl<T: ::core::fmt::Debug + ?Sized> ::core::fmt::Display for DebugAsDisplay<'_, T> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    ::core::fmt::Debug::fmt(self.0, f)
+                }
+            }
+
+            impl<T: ::core::fmt::Debug + ?Sized> ::core::fmt::Debug for DebugAsDisplay<'_, T> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    ::core::fmt::Debug::fmt(self.0, f)
+                }
+            }
+
+            let source = DebugAsDisplay(&#this.#source_field_name);
+        }
+    } else {
+        quote! { let source = &#this.#source_field_name; }
+    };
+
+    // The default formats end with where the error was created
+    let write_location = if attrs.location {
+        quote! {?; write!(f, ", at {}", #this.location) }
+    } else {
+        quote! {}
+    };
+
+    // The alternate Display ends with the help text of the kind
+    let has_help = variants
+        .iter()
+        .any(|(_, variant_attrs)| variant_attrs.help.is_some());
+    let write_help = if has_help {
+        quote! {?;
+            match (f.alternate(), self.help()) {
+                (true, Some(help)) => write!(f, "\nhelp: {}", help),
+                _ => Ok(()),
             }
         }
     } else {
@@ -202,55 +1602,3467 @@ fn kind_error_impl(input: DeriveInput) -> Result<TokenStream, syn::Error> {
     };
 
     // Handle Display implementation
-    let display_impl = if let Some(display_format) = attrs.display {
+    let display_impl = if let Some(display_format) = &attrs.display {
         // Use the user-provided formatting template directly
         quote! {
-            impl ::core::fmt::Display for #name {
+            impl #impl_generics ::core::fmt::Display for #name #ty_generics #display_where_clause {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    #kind_binding
+                    #source_binding
+                    write!(f, #display_format)#write_help
+                }
+            }
+        }
+    } else if attrs.sources {
+        quote! {
+            impl #impl_generics ::core::fmt::Display for #name #ty_generics #display_where_clause {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    write!(f, "error kind: {:?}, sources: {:?}", #this.#kind_field_name, #this.#source_field_name)#write_location #write_help
+                }
+            }
+        }
+    } else if source_type.is_none() {
+        quote! {
+            impl #impl_generics ::core::fmt::Display for #name #ty_generics #display_where_clause {
                 fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
-                    let kind = &self.kind;
-                    let source = &self.source;
-                    write!(f, #display_format)
+                    write!(f, "error kind: {:?}", #this.#kind_field_name)#write_location #write_help
                 }
             }
         }
     } else {
         // Use default format
         quote! {
-            impl ::core::fmt::Display for #name {
+            impl #impl_generics ::core::fmt::Display for #name #ty_generics #display_where_clause {
                 fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
-                    write!(f, "error kind: {:?}, source: {:?}", self.kind, self.source)
+                    write!(f, "error kind: {:?}, source: {:?}", #this.#kind_field_name, #this.#source_field_name)#write_location #write_help
                 }
             }
         }
     };
 
-    let expand = quote! {
-        #[derive(::core::fmt::Debug)]
-        #type_vis struct #name {
-            kind: #kind_type,
-            source: #source_type,
+    let detail_impl = if let Some(display_detail) = &attrs.display_detail {
+        let detail_fn_vis = attrs
+            .detail_fn_vis
+            .clone()
+            .unwrap_or(Visibility::Public(Default::default()));
+
+        quote! {
+            impl #impl_generics #name #ty_generics #display_where_clause {
+                #detail_fn_vis fn detail(&self) -> ::std::string::String {
+                    #kind_binding
+                    #source_binding
+                    ::std::format!(#display_detail)
+                }
+            }
         }
+    } else {
+        quote! {}
+    };
+
+    let has_docs_url = variants
+        .iter()
+        .any(|(_, variant_attrs)| variant_attrs.docs_url.is_some());
+    let termination_impl = if attrs.termination {
+        let docs_url_report = if has_docs_url {
+            quote! {
+                if let Some(docs_url) = self.docs_url() {
+                    ::std::eprintln!("See: {}", docs_url);
+                }
+            }
+        } else {
+            quote! {}
+        };
+        let context_report = if attrs.context {
+            quote! {
+                for (key, value) in &#this.context {
+                    ::std::eprintln!("  {}: {}", key, value);
+                }
+            }
+        } else {
+            quote! {}
+        };
+        let related_report = if attrs.related {
+            quote! {
+                for err in &#this.related {
+                    ::std::eprintln!("Related: {}", err);
+                }
+            }
+        } else {
+            quote! {}
+        };
+        let span_trace_report = if attrs.span_trace {
+            quote! { ::std::eprintln!("\nSpan trace:\n{}", #this.span_trace); }
+        } else {
+            quote! {}
+        };
 
-        impl #name {
-            #new_vis fn new(kind: #kind_type, source: impl ::core::convert::Into<#source_type>) -> Self {
-                Self { kind, source: source.into() }
+        quote! {
+            impl #impl_generics ::std::process::Termination for #name #ty_generics #error_where_clause {
+                fn report(self) -> ::std::process::ExitCode {
+                    ::std::eprintln!("Error: {}", self);
+                    #context_report
+
+                    let mut source = ::core::error::Error::source(&self);
+                    while let Some(err) = source {
+                        ::std::eprintln!("Caused by: {}", err);
+                        source = err.source();
+                    }
+                    #related_report
+                    #docs_url_report
+                    #span_trace_report
+
+                    ::std::process::ExitCode::FAILURE
+                }
             }
+        }
+    } else {
+        quote! {}
+    };
 
-            #kind_fn_vis fn kind(&self) -> &#kind_type {
-                &self.kind
+    // Ord and Hash require Eq, so all of them are keyed by the kind only to stay consistent
+    let kind_bound_generics = |bound: syn::Path| {
+        let mut generics = generics.clone();
+        generics
+            .make_where_clause()
+            .predicates
+            .push(parse_quote!(#kind_type: #bound));
+        generics
+    };
+    let cmp_eq_impl = if attrs.ord_by_kind || attrs.cmp_by_kind {
+        let generics = kind_bound_generics(parse_quote!(::core::cmp::Eq));
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+        quote! {
+            impl #impl_generics ::core::cmp::PartialEq for #name #ty_generics #where_clause {
+                fn eq(&self, other: &Self) -> bool {
+                    #this.#kind_field_name == #other.#kind_field_name
+                }
             }
 
-            #origin_fn_vis fn origin(&self) -> &#source_type {
-                &self.source
+            impl #impl_generics ::core::cmp::Eq for #name #ty_generics #where_clause {}
+        }
+    } else {
+        quote! {}
+    };
+    let hash_impl = if attrs.cmp_by_kind {
+        let generics = kind_bound_generics(parse_quote!(::core::hash::Hash));
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+        quote! {
+            impl #impl_generics ::core::hash::Hash for #name #ty_generics #where_clause {
+                fn hash<__H: ::core::hash::Hasher>(&self, state: &mut __H) {
+                    ::core::hash::Hash::hash(&#this.#kind_field_name, state);
+                }
             }
         }
+    } else {
+        quote! {}
+    };
+    let ord_impl = if attrs.ord_by_kind {
+        let generics = kind_bound_generics(parse_quote!(::core::cmp::Ord));
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-        #display_impl
+        quote! {
+            impl #impl_generics ::core::cmp::PartialOrd for #name #ty_generics #where_clause {
+                fn partial_cmp(&self, other: &Self) -> Option<::core::cmp::Ordering> {
+                    Some(::core::cmp::Ord::cmp(self, other))
+                }
+            }
 
-        impl ::core::error::Error for #name {
-            #source_method
+            impl #impl_generics ::core::cmp::Ord for #name #ty_generics #where_clause {
+                fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+                    ::core::cmp::Ord::cmp(&#this.#kind_field_name, &#other.#kind_field_name)
+                }
+            }
         }
+    } else {
+        quote! {}
     };
 
-    Ok(expand.into())
+    // `err == Kind::Variant` compares the kind, like matching on `io::Error::kind()`
+    let eq_kind_impl = if attrs.eq_kind {
+        let mut generics = generics.clone();
+        generics
+            .make_where_clause()
+            .predicates
+            .push(parse_quote!(#kind_type: ::core::cmp::PartialEq));
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+        quote! {
+            impl #impl_generics ::core::cmp::PartialEq<#kind_type> for #name #ty_generics #where_clause {
+                fn eq(&self, other: &#kind_type) -> bool {
+                    #this.#kind_field_name == *other
+                }
+            }
+
+            impl #impl_generics ::core::cmp::PartialEq<#name #ty_generics> for #kind_type #where_clause {
+                fn eq(&self, other: &#name #ty_generics) -> bool {
+                    *self == #other.#kind_field_name
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let code_methods = code_methods(
+        &attrs,
+        &variants,
+        kind_ident,
+        &kind_type,
+        &kind_fn_vis,
+        &this_kind,
+    )?;
+
+    // `description()` has to return a borrowed string, so it reports the variant name
+    let legacy_error_methods = if attrs.legacy_error_methods {
+        let description_arms = variants.iter().map(|(variant, _)| {
+            let ident = &variant.ident;
+            let description = match attrs.rename_all {
+                Some(rename_all) => rename_all.apply(&ident.to_string()),
+                None => ident.to_string(),
+            };
+            quote! { #kind_ident::#ident { .. } => #description }
+        });
+
+        quote! {
+            #[allow(deprecated)]
+            fn description(&self) -> &str {
+                match &#this.#kind_field_name {
+                    #(#description_arms,)*
+                }
+            }
+
+            #[allow(deprecated)]
+            fn cause(&self) -> Option<&dyn ::core::error::Error> {
+                ::core::error::Error::source(self)
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // For APIs taking `impl AsRef<dyn Error>`
+    let as_ref_error_impl = if attrs.as_ref_error {
+        let mut generics = error_generics.clone();
+        generics.make_where_clause().predicates.push(parse_quote!(
+            #name #ty_generics: ::core::marker::Send + ::core::marker::Sync + 'static
+        ));
+        let where_clause = &generics.where_clause;
+
+        quote! {
+            impl #impl_generics ::core::convert::AsRef<dyn ::core::error::Error + ::core::marker::Send + ::core::marker::Sync + 'static> for #name #ty_generics #where_clause {
+                fn as_ref(&self) -> &(dyn ::core::error::Error + ::core::marker::Send + ::core::marker::Sync + 'static) {
+                    self
+                }
+            }
+
+            impl #impl_generics ::core::convert::AsRef<dyn ::core::error::Error + 'static> for #name #ty_generics #where_clause {
+                fn as_ref(&self) -> &(dyn ::core::error::Error + 'static) {
+                    self
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // Fields besides the kind and source, filled in by the constructor
+    let mut extra_fields = Vec::new();
+    let mut extra_inits = Vec::new();
+    let mut extra_methods = Vec::new();
+
+    // The source as a trait object, like `io::Error::get_ref()`
+    if attrs.get_ref {
+        if !source_fn || attrs.source_lifetime.is_some() {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "get_ref requires source_fn and can't be combined with source_lifetime",
+            ));
+        }
+
+        let first_source_mut = if attrs.sources && boxed_dyn_source {
+            quote! { #this.#source_field_name.first_mut().map(|source| &mut **source) }
+        } else if attrs.sources {
+            quote! { #this.#source_field_name.first_mut() }
+        } else if optional_source && (boxed_dyn_source || box_source) {
+            quote! { #this.#source_field_name.as_deref_mut() }
+        } else if optional_source {
+            quote! { #this.#source_field_name.as_mut() }
+        } else if boxed_dyn_source || box_source {
+            quote! { Some(&mut *#this.#source_field_name) }
+        } else if arc_source {
+            // A shared source can only be borrowed mutably while no clone of the error is alive
+            quote! { ::std::sync::Arc::get_mut(&mut #this.#source_field_name) }
+        } else if source_enum.is_some() {
+            quote! { Some(#this.#source_field_name.as_error_mut()) }
+        } else {
+            quote! { Some(&mut #this.#source_field_name) }
+        };
+        let source_bound = source_bound.as_slice();
+
+        extra_methods.push(quote! {
+            #origin_fn_vis fn get_ref(&self) -> ::core::option::Option<&(dyn ::core::error::Error + 'static)>
+            where
+                #(#source_bound,)*
+            {
+                #first_source.map(|source| source as &(dyn ::core::error::Error + 'static))
+            }
+
+            #origin_fn_vis fn get_mut(&mut self) -> ::core::option::Option<&mut (dyn ::core::error::Error + 'static)>
+            where
+                #(#source_bound,)*
+            {
+                #first_source_mut.map(|source| source as &mut (dyn ::core::error::Error + 'static))
+            }
+        });
+    }
+
+    // User declared fields are taken by `new()` in declaration order, while the other
+    // constructors fill them with their defaults
+    const RESERVED_FIELDS: &[&str] = &[
+        "kind",
+        "source",
+        "sources",
+        "instant",
+        "system_time",
+        "backtrace",
+        "span_trace",
+        "location",
+        "context",
+        "message",
+        "related",
+    ];
+    // Renamed kind and source fields must not take the name of another field either
+    for renamed in [&attrs.kind_field, &attrs.source_field]
+        .into_iter()
+        .flatten()
+    {
+        if kind_field_name == source_field_name
+            || RESERVED_FIELDS
+                .iter()
+                .any(|reserved| renamed == reserved && *reserved != "kind" && *reserved != "source")
+            || attrs.extra_fields.iter().any(|(ident, _)| ident == renamed)
+        {
+            return Err(syn::Error::new_spanned(
+                renamed,
+                format!("field name {} clashes with another field", renamed),
+            ));
+        }
+    }
+    let mut user_params = Vec::with_capacity(attrs.extra_fields.len());
+    let mut field_accessors = Vec::with_capacity(attrs.extra_fields.len());
+    for (ident, ty) in &attrs.extra_fields {
+        if RESERVED_FIELDS.iter().any(|reserved| ident == reserved) {
+            return Err(syn::Error::new_spanned(
+                ident,
+                format!("extra field {} clashes with a generated field", ident),
+            ));
+        }
+        if attrs
+            .extra_fields
+            .iter()
+            .filter(|(other, _)| other == ident)
+            .count()
+            > 1
+        {
+            return Err(syn::Error::new_spanned(
+                ident,
+                format!("extra field {} is declared more than once", ident),
+            ));
+        }
+
+        extra_fields.push((ident.clone(), quote! { #ty }));
+        extra_inits.push(quote! { #ident });
+        field_accessors.push((
+            ident,
+            quote! {
+                #kind_fn_vis fn #ident(&self) -> &#ty {
+                    &#this.#ident
+                }
+            },
+        ));
+        user_params.push(quote! { #ident: #ty });
+    }
+    let default_fields = attrs
+        .extra_fields
+        .iter()
+        .map(|_| quote! { , ::core::default::Default::default() })
+        .collect::<proc_macro2::TokenStream>();
+
+    if attrs.related {
+        let related_type = quote! {
+            ::std::boxed::Box<dyn ::core::error::Error + ::core::marker::Send + ::core::marker::Sync>
+        };
+
+        extra_fields.push((
+            format_ident!("related"),
+            quote! { ::std::vec::Vec<#related_type> },
+        ));
+        extra_inits.push(quote! { related: ::std::vec::Vec::new() });
+        extra_methods.push(quote! {
+            #new_vis fn push_related(&mut self, err: impl ::core::convert::Into<#related_type>) {
+                #this.related.push(err.into());
+            }
+
+            #origin_fn_vis fn related(&self) -> &[#related_type] {
+                &#this.related
+            }
+        });
+    }
+
+    if attrs.message {
+        extra_fields.push((
+            format_ident!("message"),
+            quote! { ::std::borrow::Cow<'static, str> },
+        ));
+        extra_inits.push(quote! { message: ::std::borrow::Cow::Borrowed("") });
+        extra_methods.push(quote! {
+            #kind_fn_vis fn message(&self) -> &str {
+                &#this.message
+            }
+        });
+    }
+
+    if attrs.context {
+        extra_fields.push((
+            format_ident!("context"),
+            quote! { ::std::vec::Vec<(&'static str, ::std::string::String)> },
+        ));
+        extra_inits.push(quote! { context: ::std::vec::Vec::new() });
+        extra_methods.push(quote! {
+            #new_vis fn with_context(mut self, key: &'static str, value: impl ::std::string::ToString) -> Self {
+                #this.context.push((key, value.to_string()));
+                self
+            }
+
+            #kind_fn_vis fn context(&self) -> &[(&'static str, ::std::string::String)] {
+                &#this.context
+            }
+        });
+    }
+
+    if attrs.timestamp {
+        let timestamp_fn_vis = attrs
+            .timestamp_fn_vis
+            .clone()
+            .unwrap_or(Visibility::Public(Default::default()));
+
+        // The monotonic instant measures the age of the error, while the wall clock time
+        // says when it happened
+        let now = match &attrs.clock_fn {
+            Some(clock_fn) => quote! { #clock_fn() },
+            None => quote! { ::std::time::SystemTime::now() },
+        };
+
+        extra_fields.push((format_ident!("instant"), quote! { ::std::time::Instant }));
+        extra_fields.push((
+            format_ident!("system_time"),
+            quote! { ::std::time::SystemTime },
+        ));
+        extra_inits.push(quote! { instant: ::std::time::Instant::now() });
+        extra_inits.push(quote! { system_time: #now });
+        extra_methods.push(quote! {
+            #timestamp_fn_vis fn created_at(&self) -> ::std::time::Instant {
+                #this.instant
+            }
+
+            #timestamp_fn_vis fn elapsed(&self) -> ::core::time::Duration {
+                #this.instant.elapsed()
+            }
+
+            #timestamp_fn_vis fn occurred_at(&self) -> ::std::time::SystemTime {
+                #this.system_time
+            }
+        });
+    } else if let Some(clock_fn) = &attrs.clock_fn {
+        return Err(syn::Error::new_spanned(
+            clock_fn,
+            "clock_fn requires timestamp = true",
+        ));
+    }
+
+    if let Some((lit_str, _)) = &attrs.backtrace_policy
+        && !attrs.backtrace
+    {
+        return Err(syn::Error::new_spanned(
+            lit_str,
+            "backtrace_policy requires backtrace = true",
+        ));
+    }
+
+    if attrs.backtrace {
+        let backtrace_fn_vis = attrs
+            .backtrace_fn_vis
+            .clone()
+            .unwrap_or(Visibility::Public(Default::default()));
+
+        extra_fields.push((
+            format_ident!("backtrace"),
+            quote! { ::std::backtrace::Backtrace },
+        ));
+        let capture = match attrs.backtrace_policy.as_ref().map(|(_, policy)| policy) {
+            None | Some(BacktracePolicy::Always) => {
+                quote! { ::std::backtrace::Backtrace::force_capture() }
+            }
+            Some(BacktracePolicy::Env) => quote! { ::std::backtrace::Backtrace::capture() },
+            Some(BacktracePolicy::EnvVar(var)) => quote! {
+                if ::std::env::var_os(#var).is_some_and(|value| value != "0") {
+                    ::std::backtrace::Backtrace::force_capture()
+                } else {
+                    ::std::backtrace::Backtrace::disabled()
+                }
+            },
+        };
+        extra_inits.push(quote! { backtrace: #capture });
+        extra_methods.push(quote! {
+            #backtrace_fn_vis fn backtrace(&self) -> &::std::backtrace::Backtrace {
+                &#this.backtrace
+            }
+        });
+    }
+
+    if attrs.location {
+        extra_fields.push((
+            format_ident!("location"),
+            quote! { &'static ::core::panic::Location<'static> },
+        ));
+        extra_inits.push(quote! { location: ::core::panic::Location::caller() });
+        extra_methods.push(quote! {
+            #kind_fn_vis fn location(&self) -> &'static ::core::panic::Location<'static> {
+                #this.location
+            }
+        });
+    }
+
+    if attrs.span_trace {
+        let backtrace_fn_vis = attrs
+            .backtrace_fn_vis
+            .clone()
+            .unwrap_or(Visibility::Public(Default::default()));
+
+        extra_fields.push((
+            format_ident!("span_trace"),
+            quote! { ::tracing_error::SpanTrace },
+        ));
+        extra_inits.push(quote! { span_trace: ::tracing_error::SpanTrace::capture() });
+        extra_methods.push(quote! {
+            #backtrace_fn_vis fn span_trace(&self) -> &::tracing_error::SpanTrace {
+                &#this.span_trace
+            }
+        });
+    }
+
+    // Per-variant method names follow rename_all, in snake_case by default
+    let method_case = attrs.rename_all.unwrap_or(RenameRule::Snake);
+    let method_case_attrs = match method_case {
+        RenameRule::Snake => quote! {},
+        _ => quote! { #[allow(non_snake_case)] },
+    };
+
+    // With per-variant sources, each fieldless variant gets a constructor taking its own source type
+    if let (Some(_), Some(source_type)) = (&source_enum, &source_type) {
+        for (variant, variant_attrs) in unskipped(&variants) {
+            if !matches!(variant.fields, syn::Fields::Unit) {
+                continue;
+            }
+
+            let ident = &variant.ident;
+            let variant_source = variant_attrs.source.as_ref().unwrap_or(source_type);
+            let new_variant = method_case.method("New", ident);
+            extra_methods.push(quote! {
+                #ctor_attrs
+                #method_case_attrs
+                #new_vis fn #new_variant(source: impl ::core::convert::Into<#variant_source>) -> Self {
+                    Self::#new_ident(#kind_ident::#ident, ::core::convert::Into::<#variant_source>::into(source) #default_fields)
+                }
+            });
+        }
+    }
+
+    if has_docs_url {
+        let docs_url = variant_str_method(&variants, kind_ident, &this_kind, |variant_attrs| {
+            variant_attrs.docs_url.as_ref()
+        });
+        extra_methods.push(quote! {
+            #kind_fn_vis fn docs_url(&self) -> ::core::option::Option<&'static str> {
+                #docs_url
+            }
+        });
+    }
+
+    if has_help {
+        let help = variant_str_method(&variants, kind_ident, &this_kind, |variant_attrs| {
+            variant_attrs.help.as_ref()
+        });
+        extra_methods.push(quote! {
+            #kind_fn_vis fn help(&self) -> ::core::option::Option<&'static str> {
+                #help
+            }
+        });
+    }
+
+    // Source methods forwarded by the error, so the source itself can stay private
+    if let Some(first_delegate) = attrs.delegate.first()
+        && (source_type.is_none() || attrs.sources || optional_source || source_enum.is_some())
+    {
+        return Err(syn::Error::new_spanned(
+            &first_delegate.sig,
+            "delegate requires a single source, so it can't be combined with sources, an optional source or per-variant source types",
+        ));
+    }
+    for DelegateFn { vis, sig, rename } in &attrs.delegate {
+        let target = &sig.ident;
+        let args = sig
+            .inputs
+            .iter()
+            .filter_map(|input| match input {
+                syn::FnArg::Typed(pat_type) => Some(match &*pat_type.pat {
+                    syn::Pat::Ident(pat_ident) => Ok(&pat_ident.ident),
+                    pat => Err(syn::Error::new_spanned(
+                        pat,
+                        "delegated method arguments must be plain identifiers",
+                    )),
+                }),
+                syn::FnArg::Receiver(_) => None,
+            })
+            .collect::<syn::Result<Vec<_>>>()?;
+        let mut sig = sig.clone();
+        if let Some(rename) = rename {
+            sig.ident = rename.clone();
+        }
+
+        extra_methods.push(quote! {
+            #vis #sig {
+                #this.#source_field_name.#target(#(#args),*)
+            }
+        });
+    }
+
+    // Payload accessors for data-carrying variants
+    if attrs.as_fns {
+        for (variant, _) in unskipped(&variants) {
+            if variant.fields.is_empty() {
+                continue;
+            }
+
+            let ident = &variant.ident;
+            let as_variant = method_case.method("As", ident);
+            let field_idents = variant
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(i, field)| {
+                    field
+                        .ident
+                        .clone()
+                        .unwrap_or_else(|| format_ident!("field_{}", i))
+                })
+                .collect::<Vec<_>>();
+            let field_types = variant.fields.iter().map(|field| &field.ty);
+            let pattern = match &variant.fields {
+                syn::Fields::Named(_) => quote! { #kind_ident::#ident { #(#field_idents),* } },
+                _ => quote! { #kind_ident::#ident(#(#field_idents),*) },
+            };
+            let (return_type, value) = if field_idents.len() == 1 {
+                (quote! { #(&#field_types)* }, quote! { #(#field_idents)* })
+            } else {
+                (
+                    quote! { (#(&#field_types),*) },
+                    quote! { (#(#field_idents),*) },
+                )
+            };
+
+            extra_methods.push(quote! {
+                #method_case_attrs
+                #kind_fn_vis fn #as_variant(&self) -> ::core::option::Option<#return_type> {
+                    match &#this.#kind_field_name {
+                        #pattern => Some(#value),
+                        #[allow(unreachable_patterns)]
+                        _ => None,
+                    }
+                }
+            });
+        }
+    }
+
+    // Variant predicates on the error, and optionally on the kind itself
+    let is_fns = unskipped(&variants)
+        .map(|(variant, _)| {
+            let ident = &variant.ident;
+            let is_variant = method_case.method("Is", ident);
+            (ident, is_variant)
+        })
+        .collect::<Vec<_>>();
+    if attrs.is_fns {
+        for (ident, is_variant) in &is_fns {
+            extra_methods.push(quote! {
+                #method_case_attrs
+                #kind_fn_vis fn #is_variant(&self) -> bool {
+                    ::core::matches!(#this.#kind_field_name, #kind_ident::#ident { .. })
+                }
+            });
+        }
+    }
+    let kind_is_fns_impl = if attrs.kind_is_fns {
+        let fns = is_fns.iter().map(|(ident, is_variant)| {
+            quote! {
+                #method_case_attrs
+                #kind_fn_vis fn #is_variant(&self) -> bool {
+                    ::core::matches!(self, Self::#ident { .. })
+                }
+            }
+        });
+
+        quote! {
+            impl #impl_generics #kind_type #where_clause {
+                #(#fns)*
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    if attrs.chain {
+        extra_methods.push(quote! {
+            #origin_fn_vis fn chain(&self) -> impl ::core::iter::Iterator<Item = &(dyn ::core::error::Error + 'static)>
+            where
+                Self: ::core::error::Error + 'static,
+            {
+                let mut next: ::core::option::Option<&(dyn ::core::error::Error + 'static)> = Some(self);
+                ::core::iter::from_fn(move || {
+                    let err = next?;
+                    next = err.source();
+                    Some(err)
+                })
+            }
+
+            #origin_fn_vis fn root_cause(&self) -> &(dyn ::core::error::Error + 'static)
+            where
+                Self: ::core::error::Error + 'static,
+            {
+                let mut err: &(dyn ::core::error::Error + 'static) = self;
+                while let Some(source) = err.source() {
+                    err = source;
+                }
+                err
+            }
+        });
+    }
+
+    // Errors of this type found along the source chain are the layers it was wrapped in
+    if attrs.kind_chain {
+        extra_methods.push(quote! {
+            #kind_fn_vis fn kind_chain(&self) -> impl ::core::iter::Iterator<Item = &#kind_type>
+            where
+                Self: ::core::error::Error + 'static,
+            {
+                let mut next: ::core::option::Option<&(dyn ::core::error::Error + 'static)> = Some(self);
+                ::core::iter::from_fn(move || {
+                    loop {
+                        let err = next?;
+                        next = err.source();
+                        if let Some(error) = err.downcast_ref::<Self>() {
+                            return Some(&#error.#kind_field_name);
+                        }
+                    }
+                })
+            }
+        });
+    }
+
+    if attrs.discriminant {
+        extra_methods.push(quote! {
+            #kind_fn_vis fn kind_discriminant(&self) -> ::core::mem::Discriminant<#kind_type> {
+                ::core::mem::discriminant(&#this.#kind_field_name)
+            }
+        });
+    }
+
+    if attrs.all_kinds {
+        if let Some((variant, _)) =
+            unskipped(&variants).find(|(variant, _)| !matches!(variant.fields, syn::Fields::Unit))
+        {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "all_kinds requires every variant to be fieldless, as a variant carrying fields can't be listed without values",
+            ));
+        }
+
+        let variant_idents = unskipped(&variants).map(|(variant, _)| &variant.ident);
+        extra_methods.push(quote! {
+            #kind_fn_vis fn all_kinds() -> &'static [#kind_type] {
+                &[#(#kind_ident::#variant_idents),*]
+            }
+        });
+    }
+
+    if let Some(rename_all) = attrs.rename_all {
+        let name_arms = variants.iter().map(|(variant, _)| {
+            let ident = &variant.ident;
+            let kind_name = rename_all.apply(&ident.to_string());
+            quote! { #kind_ident::#ident { .. } => #kind_name }
+        });
+
+        extra_methods.push(quote! {
+            #kind_fn_vis fn kind_name(&self) -> &'static str {
+                match &#this.#kind_field_name {
+                    #(#name_arms,)*
+                }
+            }
+        });
+    }
+
+    if let Some(report_fn) = &attrs.report_fn {
+        if unskipped(&variants).any(|(_, variant_attrs)| variant_attrs.code.is_none()) {
+            return Err(syn::Error::new_spanned(
+                report_fn,
+                "report_fn requires a code attribute on every variant",
+            ));
+        }
+
+        // The report type is the path the constructor function is associated with
+        let mut report_type = report_fn.clone();
+        if report_type.segments.pop().is_none() || report_type.segments.is_empty() {
+            return Err(syn::Error::new_spanned(
+                report_fn,
+                "report_fn must be an associated function path, e.g. \"Report::from_parts\"",
+            ));
+        }
+        report_type.segments.pop_punct();
+
+        extra_methods.push(quote! {
+            #kind_fn_vis fn to_report(&self) -> #report_type {
+                #report_fn(self.code(), ::std::string::ToString::to_string(self))
+            }
+        });
+    }
+
+    // A const constructor can't call `Into::into()` or capture runtime state
+    let new_constness = if attrs.const_new {
+        if attrs.timestamp {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "const_new can't be combined with timestamp, as the creation time is captured at runtime",
+            ));
+        }
+        if attrs.backtrace || attrs.span_trace || attrs.location {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "const_new can't be combined with backtrace, span_trace or location, as they are captured at runtime",
+            ));
+        }
+        if attrs.kind_into {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "const_new can't be combined with kind_into, as the kind conversion runs at runtime",
+            ));
+        }
+        if let Some(validate_fn) = &attrs.validate_fn {
+            return Err(syn::Error::new_spanned(
+                validate_fn,
+                "const_new can't be combined with validate_fn, as the validation runs at runtime",
+            ));
+        }
+        if let Some(inspect_fn) = &attrs.inspect_fn {
+            return Err(syn::Error::new_spanned(
+                inspect_fn,
+                "const_new can't be combined with inspect_fn, as the hook runs at runtime",
+            ));
+        }
+
+        quote! { const }
+    } else {
+        quote! {}
+    };
+
+    // All three are empty for a kind-only error
+    let (new_param, source_ident, new_source) = if source_type.is_none() {
+        (None, None, None)
+    } else if attrs.sources {
+        (
+            Some(quote! { sources: ::std::vec::Vec<#source_type> }),
+            Some(format_ident!("sources")),
+            Some(quote! { sources }),
+        )
+    } else if optional_source || attrs.const_new {
+        (
+            Some(quote! { source: #stored_source_type }),
+            Some(format_ident!("source")),
+            Some(quote! { source }),
+        )
+    } else {
+        (
+            Some(quote! { source: impl ::core::convert::Into<#stored_source_type> }),
+            Some(format_ident!("source")),
+            Some(quote! { source.into() }),
+        )
+    };
+    let (source_ident, new_source) = (source_ident.as_slice(), new_source.as_slice());
+    let source_field_names = source_ident
+        .iter()
+        .map(|_| &source_field_name)
+        .collect::<Vec<_>>();
+    let new_params = new_param.iter().chain(&user_params).collect::<Vec<_>>();
+    let stored_source = source_ident
+        .iter()
+        .map(|ident| store_source(quote! { #ident }))
+        .collect::<Vec<_>>();
+    let stored_new_source = new_source
+        .iter()
+        .map(|source| store_source(source.clone()))
+        .collect::<Vec<_>>();
+
+    // Every constructor builds the error through this, so the inspect hook sees all of them
+    let build_error = construct(
+        quote! { #kind_field_name: kind, #(#source_field_names: #stored_source,)* #(#extra_inits,)* },
+    );
+    let build_error = if let Some(inspect_fn) = &attrs.inspect_fn {
+        quote! {{
+            let error = #build_error;
+            #inspect_fn(&#error.#kind_field_name #(, &#error.#source_field_names)*);
+            error
+        }}
+    } else {
+        build_error
+    };
+
+    // `new()` and `try_new()` may take anything convertible into the kind
+    let (new_kind_param, convert_kind) = if attrs.kind_into {
+        (
+            quote! { impl ::core::convert::Into<#kind_type> },
+            quote! { let kind = ::core::convert::Into::<#kind_type>::into(kind); },
+        )
+    } else {
+        (quote! { #kind_type }, quote! {})
+    };
+
+    let new_fn = if attrs.validate_fn.is_some() || attrs.inspect_fn.is_some() {
+        let validate_call = attrs
+            .validate_fn
+            .as_ref()
+            .map(|validate_fn| quote! { #validate_fn(&kind #(, &#source_ident)*); });
+
+        quote! {
+            #ctor_attrs
+            #new_fn_allow
+            #new_fn_vis fn #new_ident(kind: #new_kind_param, #(#new_params),*) -> Self {
+                #convert_kind
+                #(let #source_ident = #new_source;)*
+                #validate_call
+                #build_error
+            }
+        }
+    } else {
+        let build_error = construct(
+            quote! { #kind_field_name: kind, #(#source_field_names: #stored_new_source,)* #(#extra_inits,)* },
+        );
+
+        quote! {
+            #ctor_attrs
+            #new_fn_allow
+            #new_fn_vis #new_constness fn #new_ident(kind: #new_kind_param, #(#new_params),*) -> Self {
+                #convert_kind
+                #build_error
+            }
+        }
+    };
+
+    // Shortcut constructors named after the variants, taking the variant fields after the source
+    let mut variant_ctors = Vec::new();
+    if !attrs.variant_ctors
+        && let Some((alias, _)) = variants
+            .iter()
+            .find_map(|(_, variant_attrs)| variant_attrs.alias.first())
+    {
+        return Err(syn::Error::new_spanned(
+            alias,
+            "alias requires variant_ctors, as it names an old variant constructor",
+        ));
+    }
+    if attrs.variant_ctors {
+        for (variant, variant_attrs) in unskipped(&variants) {
+            let ident = &variant.ident;
+            let ctor = method_case.method("", ident);
+
+            let (source_param, source_arg) = match (&source_enum, variant_attrs.source.as_ref()) {
+                (Some(_), Some(variant_source)) => (
+                    Some(quote! { source: impl ::core::convert::Into<#variant_source> }),
+                    Some(quote! { ::core::convert::Into::<#variant_source>::into(source) }),
+                ),
+                _ => (
+                    new_param.clone(),
+                    source_ident.first().map(|ident| quote! { #ident }),
+                ),
+            };
+            let (source_param, source_arg) = (source_param.as_slice(), source_arg.as_slice());
+
+            let field_idents = variant
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(i, field)| {
+                    field
+                        .ident
+                        .clone()
+                        .unwrap_or_else(|| format_ident!("field_{}", i))
+                })
+                .collect::<Vec<_>>();
+            let field_types = variant
+                .fields
+                .iter()
+                .map(|field| &field.ty)
+                .collect::<Vec<_>>();
+            let kind = match &variant.fields {
+                syn::Fields::Named(_) => quote! { #kind_ident::#ident { #(#field_idents),* } },
+                syn::Fields::Unnamed(_) => quote! { #kind_ident::#ident(#(#field_idents),*) },
+                syn::Fields::Unit => quote! { #kind_ident::#ident },
+            };
+
+            variant_ctors.push(VariantCtor {
+                variant,
+                ctor,
+                params: quote! { #(#source_param,)* #(#field_idents: #field_types),* },
+                args: quote! { #(#source_ident,)* #(#field_idents),* },
+                body: quote! { Self::#new_ident(#kind, #(#source_arg)* #default_fields) },
+                aliases: &variant_attrs.alias,
+            });
+        }
+    }
+
+    // The kind is derived from the source by a user function
+    if let Some(classify_with) = &attrs.classify_with {
+        let classified_source = match &source_type {
+            Some(source_type) if !attrs.sources && source_enum.is_none() => source_type,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    classify_with,
+                    "classify_with requires a single source type, so it can't be combined with sources or per-variant source types",
+                ));
+            }
+        };
+        let source_arg = if optional_source {
+            quote! { Some(source) }
+        } else {
+            quote! { source }
+        };
+
+        extra_methods.push(quote! {
+            #ctor_attrs
+            #new_vis fn from_source(source: impl ::core::convert::Into<#classified_source>) -> Self {
+                let source = ::core::convert::Into::<#classified_source>::into(source);
+                let kind = #classify_with(&source);
+                Self::#new_ident(kind, #source_arg #default_fields)
+            }
+        });
+    }
+
+    let from_kind_fn = if optional_source {
+        quote! {
+            #ctor_attrs
+            #new_vis #new_constness fn from_kind(kind: #kind_type) -> Self {
+                Self::#new_ident(kind, None #default_fields)
+            }
+        }
+    } else if let Some(source_default) = &attrs.source_default {
+        let source_default = if attrs.sources {
+            quote! { ::std::vec![#source_default] }
+        } else {
+            quote! { #source_default }
+        };
+
+        quote! {
+            #ctor_attrs
+            #new_vis fn from_kind(kind: #kind_type) -> Self {
+                Self::#new_ident(kind, #source_default #default_fields)
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // Building an error from the kind alone never allocates in the compact repr
+    let from_kind_impl = if compact || attrs.source_default.is_some() {
+        quote! {
+            impl #impl_generics ::core::convert::From<#kind_type> for #name #ty_generics #where_clause {
+                #ctor_attrs
+                fn from(kind: #kind_type) -> Self {
+                    Self::from_kind(kind)
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let new_with_message_fn = if attrs.message {
+        let user_idents = attrs.extra_fields.iter().map(|(ident, _)| ident);
+
+        quote! {
+            #ctor_attrs
+            #new_vis fn new_with_message(
+                kind: #kind_type,
+                #(#new_params,)*
+                message: impl ::core::convert::Into<::std::borrow::Cow<'static, str>>,
+            ) -> Self {
+                let mut error = Self::#new_ident(kind, #(#source_ident,)* #(#user_idents),*);
+                #error.message = message.into();
+                error
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // The builder collects the same arguments as `new()` plus the optional message and context
+    let builder_items = if attrs.builder {
+        let builder_name = match &attrs.builder_name {
+            Some(builder_name) => builder_name.parse::<Ident>()?,
+            None => format_ident!("{}Builder", name),
+        };
+
+        // (field type, initial value, setter parameter type, setter body, argument passed to `new()`)
+        let source = source_type.as_ref().map(|source_type| {
+            if attrs.sources {
+                (
+                    quote! { ::std::vec::Vec<#source_type> },
+                    quote! { ::std::vec::Vec::new() },
+                    quote! { #source_type },
+                    quote! { self.source.push(::core::convert::Into::into(source)); },
+                    quote! { self.source },
+                )
+            } else if optional_source {
+                (
+                    quote! { ::core::option::Option<#source_type> },
+                    quote! { None },
+                    quote! { #source_type },
+                    quote! { self.source = Some(::core::convert::Into::into(source)); },
+                    quote! { self.source },
+                )
+            } else {
+                (
+                    quote! { ::core::option::Option<#stored_source_type> },
+                    quote! { None },
+                    quote! { #stored_source_type },
+                    quote! { self.source = Some(::core::convert::Into::into(source)); },
+                    quote! { self.source? },
+                )
+            }
+        });
+        let source = source.as_slice();
+        let source_field_ty = source.iter().map(|(ty, ..)| ty);
+        let source_init = source.iter().map(|(_, init, ..)| init);
+        let source_setter = source.iter().map(|(_, _, param, body, _)| {
+            quote! {
+                #new_vis fn source(mut self, source: impl ::core::convert::Into<#param>) -> Self {
+                    #body
+                    self
+                }
+            }
+        });
+        let source_arg = source.iter().map(|(.., arg)| arg);
+
+        let user_idents = attrs
+            .extra_fields
+            .iter()
+            .map(|(ident, _)| ident)
+            .collect::<Vec<_>>();
+        let user_types = attrs
+            .extra_fields
+            .iter()
+            .map(|(_, ty)| ty)
+            .collect::<Vec<_>>();
+
+        let (message_field, message_init, message_setter, set_message) = if attrs.message {
+            (
+                quote! { message: ::core::option::Option<::std::borrow::Cow<'static, str>>, },
+                quote! { message: None, },
+                quote! {
+                    #new_vis fn message(mut self, message: impl ::core::convert::Into<::std::borrow::Cow<'static, str>>) -> Self {
+                        self.message = Some(message.into());
+                        self
+                    }
+                },
+                quote! {
+                    if let Some(message) = self.message {
+                        #error.message = message;
+                    }
+                },
+            )
+        } else {
+            (quote! {}, quote! {}, quote! {}, quote! {})
+        };
+        let (context_field, context_init, context_setter, set_context) = if attrs.context {
+            (
+                quote! { context: ::std::vec::Vec<(&'static str, ::std::string::String)>, },
+                quote! { context: ::std::vec::Vec::new(), },
+                quote! {
+                    #new_vis fn context(mut self, key: &'static str, value: impl ::std::string::ToString) -> Self {
+                        self.context.push((key, value.to_string()));
+                        self
+                    }
+                },
+                quote! { #error.context.extend(self.context); },
+            )
+        } else {
+            (quote! {}, quote! {}, quote! {}, quote! {})
+        };
+
+        extra_methods.push(quote! {
+            #new_vis fn builder() -> #builder_name #ty_generics {
+                #builder_name {
+                    kind: None,
+                    #(source: #source_init,)*
+                    #(#user_idents: ::core::default::Default::default(),)*
+                    #message_init
+                    #context_init
+                }
+            }
+        });
+
+        quote! {
+            #must_use
+            #type_vis struct #builder_name #generics #where_clause {
+                kind: ::core::option::Option<#kind_type>,
+                #(source: #source_field_ty,)*
+                #(#user_idents: #user_types,)*
+                #message_field
+                #context_field
+            }
+
+            impl #impl_generics #builder_name #ty_generics #where_clause {
+                #new_vis fn kind(mut self, kind: #kind_type) -> Self {
+                    self.kind = Some(kind);
+                    self
+                }
+
+                #(#source_setter)*
+
+                #(
+                    #new_vis fn #user_idents(mut self, #user_idents: #user_types) -> Self {
+                        self.#user_idents = #user_idents;
+                        self
+                    }
+                )*
+
+                #message_setter
+
+                #context_setter
+
+                /// Build the error, or `None` if the kind or a required source was not set.
+                #ctor_attrs
+                #must_use
+                #new_vis fn build(self) -> ::core::option::Option<#name #ty_generics> {
+                    #[allow(unused_mut)]
+                    let mut error = #name::#new_ident(self.kind?, #(#source_arg,)* #(self.#user_idents),*);
+                    #set_message
+                    #set_context
+                    Some(error)
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let try_new_fn = match (&attrs.validate_try_fn, &attrs.validate_error) {
+        (Some(validate_try_fn), Some(validate_error)) => quote! {
+            #ctor_attrs
+            #new_vis fn try_new(kind: #new_kind_param, #(#new_params),*) -> ::core::result::Result<Self, #validate_error> {
+                #convert_kind
+                #(let #source_ident = #new_source;)*
+                #validate_try_fn(&kind #(, &#source_ident)*)?;
+                Ok(#build_error)
+            }
+        },
+        (None, None) => quote! {},
+        (Some(validate_try_fn), None) => {
+            return Err(syn::Error::new_spanned(
+                validate_try_fn,
+                "validate_try_fn requires validate_error to name its error type",
+            ));
+        }
+        (None, Some(validate_error)) => {
+            return Err(syn::Error::new_spanned(
+                validate_error,
+                "validate_error requires validate_try_fn",
+            ));
+        }
+    };
+
+    let (source_field, source_methods) = if source_type.is_none() {
+        (quote! {}, quote! {})
+    } else if attrs.sources {
+        (
+            quote! { #fields_vis #source_field_name: ::std::vec::Vec<#source_type>, },
+            quote! {
+                #new_vis fn push_source(&mut self, source: impl ::core::convert::Into<#source_type>) {
+                    #this.#source_field_name.push(source.into());
+                }
+
+                #origin_fn_vis fn sources(&self) -> &[#source_type] {
+                    &#this.#source_field_name
+                }
+            },
+        )
+    } else if let Some(source_enum) = &source_enum {
+        let ident = &source_enum.ident;
+        (
+            quote! { #fields_vis #source_field_name: #ident, },
+            quote! {
+                #origin_fn_vis fn #origin_fn_name(&self) -> &(dyn ::core::error::Error + 'static) {
+                    #this.#source_field_name.as_error()
+                }
+            },
+        )
+    } else if optional_source {
+        let as_source = if box_source {
+            quote! { as_deref }
+        } else {
+            quote! { as_ref }
+        };
+
+        (
+            quote! { #fields_vis #source_field_name: #source_field_type, },
+            quote! {
+                #origin_fn_vis #accessor_constness fn #origin_fn_name(&self) -> ::core::option::Option<&#source_type> {
+                    #this.#source_field_name.#as_source()
+                }
+            },
+        )
+    } else {
+        (
+            quote! { #fields_vis #source_field_name: #source_field_type, },
+            quote! {
+                #origin_fn_vis #accessor_constness fn #origin_fn_name(&self) -> &#source_type {
+                    &#this.#source_field_name
+                }
+            },
+        )
+    };
+
+    // There is no single source to swap in sources mode
+    let replace_source_fn = if attrs.sources || source_type.is_none() {
+        quote! {}
+    } else {
+        let replace_source_fn_vis = attrs
+            .replace_source_fn_vis
+            .clone()
+            .unwrap_or_else(|| parse_quote!(pub(crate)));
+
+        let (replaced_source_type, replace) = match (optional_source, box_source) {
+            _ if arc_source => (
+                source_field_type.clone(),
+                quote! {
+                    ::core::mem::replace(&mut #this.#source_field_name, ::std::sync::Arc::new(new_source))
+                },
+            ),
+            (true, true) => (
+                stored_source_type.clone(),
+                quote! {
+                    ::core::mem::replace(&mut #this.#source_field_name, new_source.map(::std::boxed::Box::new))
+                        .map(|source| *source)
+                },
+            ),
+            (false, true) => (
+                stored_source_type.clone(),
+                quote! { ::core::mem::replace(&mut *#this.#source_field_name, new_source) },
+            ),
+            (_, false) => (
+                stored_source_type.clone(),
+                quote! { ::core::mem::replace(&mut #this.#source_field_name, new_source) },
+            ),
+        };
+
+        quote! {
+            #replace_source_fn_vis fn replace_source(&mut self, new_source: #stored_source_type) -> #replaced_source_type {
+                #replace
+            }
+        }
+    };
+
+    // Lets middleware re-classify or enrich an error in place
+    let mut_fn_vis = attrs
+        .mut_fn_vis
+        .clone()
+        .unwrap_or_else(|| parse_quote!(pub(crate)));
+    extra_methods.push(quote! {
+        #mut_fn_vis fn kind_mut(&mut self) -> &mut #kind_type {
+            &mut #this.#kind_field_name
+        }
+    });
+    if let Some(source_type) = &source_type {
+        if attrs.sources {
+            extra_methods.push(quote! {
+                #mut_fn_vis fn sources_mut(&mut self) -> &mut ::std::vec::Vec<#source_type> {
+                    &mut #this.#source_field_name
+                }
+            });
+        } else if optional_source {
+            let as_source_mut = if box_source {
+                quote! { as_deref_mut }
+            } else {
+                quote! { as_mut }
+            };
+            extra_methods.push(quote! {
+                #mut_fn_vis fn source_mut(&mut self) -> ::core::option::Option<&mut #source_type> {
+                    #this.#source_field_name.#as_source_mut()
+                }
+            });
+        } else if source_enum.is_none() && !arc_source {
+            let source_mut = if box_source {
+                quote! { &mut *#this.#source_field_name }
+            } else {
+                quote! { &mut #this.#source_field_name }
+            };
+            extra_methods.push(quote! {
+                #mut_fn_vis fn source_mut(&mut self) -> &mut #source_type {
+                    #source_mut
+                }
+            });
+        }
+    }
+
+    // Moves the kind and source out, unboxing a boxed source
+    if attrs.into_fns {
+        extra_methods.push(quote! {
+            #kind_fn_vis fn into_kind(self) -> #kind_type {
+                #this.#kind_field_name
+            }
+        });
+    }
+    if attrs.map_fns {
+        extra_methods.push(quote! {
+            #kind_fn_vis fn map_kind(mut self, f: impl ::core::ops::FnOnce(#kind_type) -> #kind_type) -> Self {
+                #this.#kind_field_name = f(#this.#kind_field_name);
+                self
+            }
+        });
+    }
+    if let Some(source_type) = &source_type {
+        let (parts_source_type, parts_source) = if attrs.sources {
+            (
+                quote! { ::std::vec::Vec<#source_type> },
+                quote! { #this.#source_field_name },
+            )
+        } else if optional_source && box_source {
+            (
+                quote! { #stored_source_type },
+                quote! { #this.#source_field_name.map(|source| *source) },
+            )
+        } else if box_source {
+            (
+                quote! { #stored_source_type },
+                quote! { *#this.#source_field_name },
+            )
+        } else if arc_source {
+            (
+                quote! { #source_field_type },
+                quote! { #this.#source_field_name },
+            )
+        } else {
+            (
+                quote! { #stored_source_type },
+                quote! { #this.#source_field_name },
+            )
+        };
+
+        if arc_source {
+            extra_methods.push(quote! {
+                #origin_fn_vis fn shared(self) -> ::std::sync::Arc<Self> {
+                    ::std::sync::Arc::new(self)
+                }
+            });
+        }
+
+        if attrs.into_parts {
+            extra_methods.push(quote! {
+                #origin_fn_vis fn into_parts(self) -> (#kind_type, #parts_source_type) {
+                    (#this.#kind_field_name, #parts_source)
+                }
+            });
+        }
+
+        if attrs.into_fns {
+            extra_methods.push(quote! {
+                #origin_fn_vis fn into_source(self) -> #parts_source_type {
+                    #parts_source
+                }
+            });
+        }
+
+        if attrs.map_fns {
+            let mapped_source = if arc_source {
+                quote! { f(#parts_source) }
+            } else {
+                store_source(quote! { f(#parts_source) })
+            };
+
+            extra_methods.push(quote! {
+                #origin_fn_vis fn map_source(mut self, f: impl ::core::ops::FnOnce(#parts_source_type) -> #parts_source_type) -> Self {
+                    #this.#source_field_name = #mapped_source;
+                    self
+                }
+
+                #origin_fn_vis fn map_source_into<MappedError, MappedKind, MappedSource>(
+                    self,
+                    f: impl ::core::ops::FnOnce(#parts_source_type) -> MappedSource,
+                ) -> MappedError
+                where
+                    MappedKind: ::core::convert::From<#kind_type>,
+                    MappedError: ::core::convert::From<(MappedKind, MappedSource)>,
+                {
+                    MappedError::from((MappedKind::from(#this.#kind_field_name), f(#parts_source)))
+                }
+            });
+        }
+    }
+
+    let source_enum_items = source_enum.as_ref().map(|source_enum| source_enum.expand());
+
+    let extra_field_defs = extra_fields
+        .iter()
+        .map(|(ident, ty)| quote! { #fields_vis #ident: #ty });
+
+    // io::Error isn't Clone, but can be rebuilt from its kind and message, while a shared source
+    // only bumps its reference count
+    let io_clone_impl = if attrs.io_clone || arc_source {
+        if attrs.io_clone && !source_type.as_ref().is_some_and(is_io_error) {
+            return Err(syn::Error::new_spanned(
+                &source_type,
+                "io_clone requires the source to be std::io::Error",
+            ));
+        }
+        if attrs.backtrace || attrs.related {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                format!(
+                    "{} can't be combined with backtrace or related, as they aren't Clone",
+                    if arc_source { "arc_source" } else { "io_clone" }
+                ),
+            ));
+        }
+
+        let clone_source = if arc_source {
+            quote! {
+                #source_field_name: ::std::sync::Arc::clone(&#this.#source_field_name)
+            }
+        } else if attrs.sources {
+            quote! {
+                #source_field_name: #this
+                    .#source_field_name
+                    .iter()
+                    .map(|source| ::std::io::Error::new(source.kind(), source.to_string()))
+                    .collect()
+            }
+        } else if optional_source {
+            let source = store_source(quote! {
+                #this.#source_field_name
+                    .as_ref()
+                    .map(|source| ::std::io::Error::new(source.kind(), source.to_string()))
+            });
+            quote! { #source_field_name: #source }
+        } else {
+            let source = store_source(quote! {
+                ::std::io::Error::new(#this.#source_field_name.kind(), #this.#source_field_name.to_string())
+            });
+            quote! { #source_field_name: #source }
+        };
+        let extra_clones = extra_fields.iter().map(|(ident, _)| {
+            quote! { #ident: ::core::clone::Clone::clone(&#this.#ident) }
+        });
+
+        // Extra fields are cloned as they are, so their types have to be Clone too
+        let mut generics = generics.clone();
+        let where_clause = generics.make_where_clause();
+        where_clause
+            .predicates
+            .push(parse_quote!(#kind_type: ::core::clone::Clone));
+        where_clause.predicates.extend(
+            attrs
+                .extra_fields
+                .iter()
+                .map(|(_, ty)| -> WherePredicate { parse_quote!(#ty: ::core::clone::Clone) }),
+        );
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+        let clone = construct(quote! {
+            #kind_field_name: ::core::clone::Clone::clone(&#this.#kind_field_name),
+            #clone_source,
+            #(#extra_clones,)*
+        });
+
+        quote! {
+            impl #impl_generics ::core::clone::Clone for #name #ty_generics #where_clause {
+                fn clone(&self) -> Self {
+                    #clone
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // Converts `err` into the argument `new()` takes for its source
+    let wrap_source = if attrs.sources {
+        quote! { ::std::vec![::core::convert::Into::<#source_type>::into(err)] }
+    } else if optional_source {
+        quote! { Some(::core::convert::Into::<#source_type>::into(err)) }
+    } else {
+        quote! { ::core::convert::Into::<#source_type>::into(err) }
+    };
+
+    // Errors converted with `From` are wrapped under the default kind. Without from_sources, the
+    // source itself is converted so `?` works on calls returning it
+    let from_impls = if attrs.from_sources.is_empty() {
+        match (&attrs.default_kind, &source_type) {
+            (Some(default_kind), Some(source_type)) => quote! {
+                impl #impl_generics ::core::convert::From<#source_type> for #name #ty_generics #where_clause {
+                    #ctor_attrs
+                    fn from(err: #source_type) -> Self {
+                        Self::#new_ident(#default_kind, #wrap_source #default_fields)
+                    }
+                }
+            },
+            _ => quote! {},
+        }
+    } else {
+        let default_kind = attrs.default_kind.as_ref().ok_or_else(|| {
+            syn::Error::new_spanned(&attrs.from_sources[0], "from_sources requires default_kind")
+        })?;
+
+        let mut seen = Vec::with_capacity(attrs.from_sources.len());
+        for ty in &attrs.from_sources {
+            let ty_str = quote! { #ty }.to_string();
+            if seen.contains(&ty_str) {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    format!("{} is listed more than once in from_sources", ty_str),
+                ));
+            }
+            seen.push(ty_str);
+        }
+
+        let impls = attrs.from_sources.iter().map(|ty| {
+            quote! {
+                impl #impl_generics ::core::convert::From<#ty> for #name #ty_generics #where_clause {
+                    #ctor_attrs
+                    fn from(err: #ty) -> Self {
+                        Self::#new_ident(#default_kind, #wrap_source #default_fields)
+                    }
+                }
+            }
+        });
+
+        quote! { #(#impls)* }
+    };
+
+    // Errors listed on a variant are wrapped under that variant, so they can't overlap the
+    // conversions of the default kind
+    let mut seen_from = attrs
+        .from_sources
+        .iter()
+        .map(|ty| quote! { #ty }.to_string())
+        .collect::<Vec<_>>();
+    if let (true, Some(_), Some(source_type)) = (
+        attrs.from_sources.is_empty(),
+        &attrs.default_kind,
+        &source_type,
+    ) {
+        seen_from.push(quote! { #source_type }.to_string());
+    }
+    let mut variant_from_impls = Vec::new();
+    for (variant, variant_attrs) in &variants {
+        let Some(first_from) = variant_attrs.from.first() else {
+            continue;
+        };
+        if source_type.is_none() {
+            return Err(syn::Error::new_spanned(
+                first_from,
+                "from requires the source attribute",
+            ));
+        }
+        if !matches!(variant.fields, syn::Fields::Unit) {
+            return Err(syn::Error::new_spanned(
+                first_from,
+                "from requires a fieldless variant",
+            ));
+        }
+
+        let ident = &variant.ident;
+        let source_arg = match (&source_enum, &variant_attrs.source) {
+            (Some(_), Some(variant_source)) => {
+                quote! { ::core::convert::Into::<#variant_source>::into(err) }
+            }
+            _ => wrap_source.clone(),
+        };
+        for ty in &variant_attrs.from {
+            let ty_str = quote! { #ty }.to_string();
+            if seen_from.contains(&ty_str) {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    format!("{} is converted by more than one From impl", ty_str),
+                ));
+            }
+            seen_from.push(ty_str);
+
+            variant_from_impls.push(quote! {
+                impl #impl_generics ::core::convert::From<#ty> for #name #ty_generics #where_clause {
+                    #ctor_attrs
+                    fn from(err: #ty) -> Self {
+                        Self::#new_ident(#kind_ident::#ident, #source_arg #default_fields)
+                    }
+                }
+            });
+        }
+    }
+
+    // Any error can be boxed into the source, like `io::Error::new()` accepts any inner error
+    let catch_all_impl = if attrs.catch_all {
+        let mut generics = generics.clone();
+        generics.params.push(parse_quote!(
+            CatchAllSource: ::core::error::Error + ::core::marker::Send + ::core::marker::Sync + 'static
+        ));
+        let (from_impl_generics, _, _) = generics.split_for_impl();
+
+        extra_methods.push(quote! {
+            #ctor_attrs
+            #new_vis fn boxed(
+                kind: #kind_type,
+                err: impl ::core::error::Error + ::core::marker::Send + ::core::marker::Sync + 'static,
+            ) -> Self {
+                Self::#new_ident(kind, #wrap_source #default_fields)
+            }
+        });
+
+        quote! {
+            impl #from_impl_generics ::core::convert::From<(#kind_type, CatchAllSource)> for #name #ty_generics #where_clause {
+                #ctor_attrs
+                fn from((kind, err): (#kind_type, CatchAllSource)) -> Self {
+                    Self::#new_ident(kind, #wrap_source #default_fields)
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // Errors of other layers, including other kind errors, are re-classified under a new kind
+    if let Some(source_type) = source_type.as_ref().filter(|ty| is_boxed_dyn_error(ty)) {
+        // The boxed source can be recovered as its concrete type
+        if !attrs.sources {
+            let (source_ref, source_mut, is_source) = if optional_source {
+                (
+                    quote! { #this.#source_field_name.as_deref()? },
+                    quote! { #this.#source_field_name.as_deref_mut()? },
+                    quote! { #this.#source_field_name.as_deref().is_some_and(|source| source.is::<DowncastSource>()) },
+                )
+            } else {
+                (
+                    quote! { #this.#source_field_name },
+                    quote! { #this.#source_field_name },
+                    quote! { #this.#source_field_name.is::<DowncastSource>() },
+                )
+            };
+            let into_boxed_source = if optional_source {
+                quote! { #this.#source_field_name.unwrap_or_else(|| ::core::unreachable!()) }
+            } else {
+                quote! { #this.#source_field_name }
+            };
+
+            extra_methods.push(quote! {
+                #origin_fn_vis fn downcast_source_ref<DowncastSource: ::core::error::Error + 'static>(&self) -> ::core::option::Option<&DowncastSource> {
+                    #source_ref.downcast_ref::<DowncastSource>()
+                }
+
+                #origin_fn_vis fn downcast_source_mut<DowncastSource: ::core::error::Error + 'static>(&mut self) -> ::core::option::Option<&mut DowncastSource> {
+                    #source_mut.downcast_mut::<DowncastSource>()
+                }
+
+                #origin_fn_vis fn downcast_source<DowncastSource: ::core::error::Error + 'static>(self) -> ::core::result::Result<DowncastSource, Self> {
+                    if !(#is_source) {
+                        return Err(self);
+                    }
+                    match #into_boxed_source.downcast::<DowncastSource>() {
+                        Ok(source) => Ok(*source),
+                        Err(_) => ::core::unreachable!(),
+                    }
+                }
+            });
+        }
+
+        if let Some(default_kind) = &attrs.default_kind {
+            extra_methods.push(quote! {
+                #ctor_attrs
+                #new_vis fn other(err: impl ::core::convert::Into<#source_type>) -> Self {
+                    Self::#new_ident(#default_kind, #wrap_source #default_fields)
+                }
+            });
+        }
+
+        extra_methods.push(quote! {
+            #ctor_attrs
+            #new_vis fn wrap(
+                kind: #kind_type,
+                err: impl ::core::error::Error + ::core::marker::Send + ::core::marker::Sync + 'static,
+            ) -> Self {
+                Self::#new_ident(kind, #wrap_source #default_fields)
+            }
+        });
+    }
+
+    let from_tuple_impl = match &attrs.from_tuple {
+        Some(from_tuple) if from_tuple.value() => {
+            if source_type.is_none() || attrs.catch_all {
+                return Err(syn::Error::new_spanned(
+                    from_tuple,
+                    "from_tuple requires a source, and catch_all already implements From<(Kind, E)>",
+                ));
+            }
+
+            let tuple_source = if source_enum.is_some() {
+                stored_source_type.clone()
+            } else {
+                new_param_type(&attrs, source_type.as_ref()).into_token_stream()
+            };
+
+            quote! {
+                impl #impl_generics ::core::convert::From<(#kind_type, #tuple_source)> for #name #ty_generics #where_clause {
+                    #ctor_attrs
+                    fn from((kind, source): (#kind_type, #tuple_source)) -> Self {
+                        Self::#new_ident(kind, source #default_fields)
+                    }
+                }
+            }
+        }
+        _ => quote! {},
+    };
+
+    // A Copy kind can be returned by value, like `io::Error::kind()`
+    let kind_fn = if attrs.kind_fn && attrs.kind_by_value {
+        quote! {
+            #kind_fn_vis #accessor_constness fn #kind_fn_name(&self) -> #kind_type
+            where
+                #kind_type: ::core::marker::Copy,
+            {
+                #this.#kind_field_name
+            }
+        }
+    } else if attrs.kind_fn {
+        quote! {
+            #kind_fn_vis #accessor_constness fn #kind_fn_name(&self) -> &#kind_type {
+                &#this.#kind_field_name
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let field_count = if attrs.boxed {
+        1
+    } else {
+        1 + usize::from(source_type.is_some()) + extra_fields.len()
+    };
+    let repr_transparent = match &attrs.repr_transparent {
+        Some(repr_transparent) if repr_transparent.value() => {
+            if field_count != 1 {
+                return Err(syn::Error::new_spanned(
+                    repr_transparent,
+                    format!(
+                        "repr_transparent requires the generated struct to have exactly one field, but it has {}",
+                        field_count
+                    ),
+                ));
+            }
+
+            quote! { #[repr(transparent)] }
+        }
+        _ => quote! {},
+    };
+
+    let arbitrary_impl = if attrs.arbitrary {
+        if !generics.params.is_empty() || source_enum.is_some() {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "arbitrary can't be combined with a generic kind enum or per-variant source types",
+            ));
+        }
+
+        arbitrary_impl(
+            &variants,
+            kind_ident,
+            &name,
+            new_param_type(&attrs, source_type.as_ref()),
+            &new_ident,
+            &default_fields,
+        )
+    } else {
+        quote! {}
+    };
+
+    // The source crosses the wire as a string, and comes back as an opaque error
+    // The fields of the wire representation with their types, and whether deserializing needs them
+    let mut wire_fields = Vec::new();
+    let serde_impl = if attrs.serde {
+        if !generics.params.is_empty() || source_enum.is_some() || attrs.source_lifetime.is_some() {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "serde can't be combined with a generic kind enum, per-variant source types or source_lifetime",
+            ));
+        }
+
+        let name_str = name.to_string();
+        let kind_key = kind_field_name.to_string();
+        let source_key = source_field_name.to_string();
+        let user_idents = attrs
+            .extra_fields
+            .iter()
+            .map(|(ident, _)| ident)
+            .collect::<Vec<_>>();
+        let user_types = attrs.extra_fields.iter().map(|(_, ty)| ty);
+        let user_keys = user_idents
+            .iter()
+            .map(|ident| ident.to_string())
+            .collect::<Vec<_>>();
+
+        let stringify = |source: proc_macro2::TokenStream| {
+            if source_fn {
+                quote! { ::std::string::ToString::to_string(#source) }
+            } else {
+                quote! { ::std::format!("{:?}", #source) }
+            }
+        };
+        let unstringify = |source: proc_macro2::TokenStream| {
+            if source_type.as_ref().is_some_and(is_io_error) {
+                quote! { ::std::io::Error::other(#source) }
+            } else {
+                quote! { ::core::convert::From::from(#source) }
+            }
+        };
+        let (source_wire_type, source_string, source_value) = match &source_type {
+            None => (None, None, None),
+            Some(_) if attrs.sources => {
+                let string = stringify(quote! { source });
+                let value = unstringify(quote! { source });
+                (
+                    Some(quote! { ::std::vec::Vec<::std::string::String> }),
+                    Some(quote! {
+                        #this.#source_field_name
+                            .iter()
+                            .map(|source| #string)
+                            .collect::<::std::vec::Vec<_>>()
+                    }),
+                    Some(quote! { source.into_iter().map(|source| #value).collect() }),
+                )
+            }
+            Some(_) if optional_source => {
+                let string = stringify(quote! { source });
+                let value = unstringify(quote! { source });
+                (
+                    Some(quote! { ::core::option::Option<::std::string::String> }),
+                    Some(quote! { #this.#source_field_name.as_ref().map(|source| #string) }),
+                    Some(quote! { source.flatten().map(|source| #value) }),
+                )
+            }
+            Some(_) => (
+                Some(quote! { ::std::string::String }),
+                Some(stringify(quote! { &#this.#source_field_name })),
+                Some(unstringify(quote! { source })),
+            ),
+        };
+        let source_wire_type = source_wire_type.as_slice();
+        let source_string = source_string.as_slice();
+        let source_key = source_wire_type
+            .iter()
+            .map(|_| &source_key)
+            .collect::<Vec<_>>();
+        // Only an optional source may be left out of the input
+        let require_source = if optional_source || source_type.is_none() {
+            quote! {}
+        } else {
+            quote! {
+                let source = source.ok_or_else(|| {
+                    <__A::Error as ::serde::de::Error>::missing_field(#(#source_key)*)
+                })?;
+            }
+        };
+        let source_arg = source_value
+            .map(|source_value| quote! { #source_value, })
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        let (message_key, message_field, set_message) = if attrs.message {
+            (
+                vec![quote! { "message" }],
+                quote! { state.serialize_field("message", &*#this.message)?; },
+                quote! {
+                    if let Some(message) = message {
+                        #error.message = ::std::borrow::Cow::Owned(message);
+                    }
+                },
+            )
+        } else {
+            (Vec::new(), quote! {}, quote! {})
+        };
+        let message_var = message_key.iter().map(|_| format_ident!("message"));
+        let (context_key, context_field) = if attrs.context {
+            (
+                vec![quote! { "context" }],
+                quote! { state.serialize_field("context", &#this.context)?; },
+            )
+        } else {
+            (Vec::new(), quote! {})
+        };
+        // The code follows from the kind, so it is only written
+        let code_ty = variants
+            .iter()
+            .any(|(_, variant_attrs)| variant_attrs.code.is_some())
+            .then(|| {
+                attrs
+                    .code_ty
+                    .clone()
+                    .unwrap_or_else(|| Ident::new("u32", proc_macro2::Span::call_site()))
+            });
+        let code_key = code_ty
+            .iter()
+            .map(|_| quote! { "code" })
+            .collect::<Vec<_>>();
+        let field_count = 1
+            + source_key.len()
+            + code_key.len()
+            + user_idents.len()
+            + message_key.len()
+            + context_key.len();
+
+        wire_fields.push((kind_key.clone(), quote! { #kind_type }, true));
+        for (key, ty) in source_key.iter().zip(source_wire_type) {
+            wire_fields.push(((*key).clone(), ty.clone(), !optional_source));
+        }
+        if let Some(ty) = &code_ty {
+            wire_fields.push(("code".to_string(), quote! { #ty }, false));
+        }
+        for (key, (_, ty)) in user_keys.iter().zip(&attrs.extra_fields) {
+            wire_fields.push((key.clone(), quote! { #ty }, true));
+        }
+        if attrs.message {
+            wire_fields.push((
+                "message".to_string(),
+                quote! { ::std::string::String },
+                false,
+            ));
+        }
+        if attrs.context {
+            wire_fields.push((
+                "context".to_string(),
+                quote! { ::std::vec::Vec<(::std::string::String, ::std::string::String)> },
+                false,
+            ));
+        }
+
+        quote! {
+            impl ::serde::Serialize for #name {
+                fn serialize<__S>(&self, serializer: __S) -> ::core::result::Result<__S::Ok, __S::Error>
+                where
+                    __S: ::serde::Serializer,
+                {
+                    use ::serde::ser::SerializeStruct as _;
+
+                    let mut state = serializer.serialize_struct(#name_str, #field_count)?;
+                    state.serialize_field(#kind_key, &#this_kind)?;
+                    #(state.serialize_field(#source_key, &#source_string)?;)*
+                    #(state.serialize_field(#code_key, &self.code())?;)*
+                    #(state.serialize_field(#user_keys, &#this.#user_idents)?;)*
+                    #message_field
+                    #context_field
+                    state.end()
+                }
+            }
+
+            impl<'de> ::serde::Deserialize<'de> for #name {
+                fn deserialize<__D>(deserializer: __D) -> ::core::result::Result<Self, __D::Error>
+                where
+                    __D: ::serde::Deserializer<'de>,
+                {
+                    struct Visitor;
+
+                    impl<'de> ::serde::de::Visitor<'de> for Visitor {
+                        type Value = #name;
+
+                        fn expecting(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                            f.write_str(::core::concat!("struct ", #name_str))
+                        }
+
+                        fn visit_map<__A>(self, mut map: __A) -> ::core::result::Result<#name, __A::Error>
+                        where
+                            __A: ::serde::de::MapAccess<'de>,
+                        {
+                            let mut kind = ::core::option::Option::<#kind_type>::None;
+                            #(let mut source = ::core::option::Option::<#source_wire_type>::None;)*
+                            #(let mut #user_idents = ::core::option::Option::<#user_types>::None;)*
+                            #(let mut #message_var = ::core::option::Option::<::std::string::String>::None;)*
+                            while let Some(key) = map.next_key::<::std::string::String>()? {
+                                match key.as_str() {
+                                    #kind_key => kind = Some(map.next_value()?),
+                                    #(#source_key => source = Some(map.next_value()?),)*
+                                    #(#user_keys => #user_idents = Some(map.next_value()?),)*
+                                    #(#message_key => message = Some(map.next_value()?),)*
+                                    _ => {
+                                        map.next_value::<::serde::de::IgnoredAny>()?;
+                                    }
+                                }
+                            }
+
+                            let kind = kind.ok_or_else(|| {
+                                <__A::Error as ::serde::de::Error>::missing_field(#kind_key)
+                            })?;
+                            #require_source
+                            #(
+                                let #user_idents = #user_idents.ok_or_else(|| {
+                                    <__A::Error as ::serde::de::Error>::missing_field(#user_keys)
+                                })?;
+                            )*
+                            #[allow(unused_mut)]
+                            let mut error = #name::#new_ident(kind, #(#source_arg)* #(#user_idents),*);
+                            #set_message
+                            Ok(error)
+                        }
+                    }
+
+                    const FIELDS: &[&str] = &[#kind_key, #(#source_key,)* #(#code_key,)* #(#user_keys,)* #(#message_key,)* #(#context_key,)*];
+                    deserializer.deserialize_struct(#name_str, FIELDS, Visitor)
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let json_schema_impl = if attrs.json_schema {
+        if !attrs.serde {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "json_schema requires serde, as it describes the serialized error",
+            ));
+        }
+
+        let name_str = name.to_string();
+        let keys = wire_fields.iter().map(|(key, _, _)| key);
+        let types = wire_fields.iter().map(|(_, ty, _)| ty);
+        // Bound to locals first, as json_schema! would split a type like `HashMap<K, V>` at its comma
+        let schemas = (0..wire_fields.len())
+            .map(|index| format_ident!("schema{}", index))
+            .collect::<Vec<_>>();
+        let required = wire_fields
+            .iter()
+            .filter(|(_, _, required)| *required)
+            .map(|(key, _, _)| key);
+
+        quote! {
+            impl ::schemars::JsonSchema for #name {
+                fn schema_name() -> ::std::borrow::Cow<'static, str> {
+                    ::std::borrow::Cow::Borrowed(#name_str)
+                }
+
+                fn json_schema(generator: &mut ::schemars::SchemaGenerator) -> ::schemars::Schema {
+                    #(let #schemas = generator.subschema_for::<#types>();)*
+                    ::schemars::json_schema!({
+                        "type": "object",
+                        "properties": {
+                            #(#keys: #schemas,)*
+                        },
+                        "required": [#(#required),*]
+                    })
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let utoipa_impl = if attrs.utoipa {
+        if !attrs.serde {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "utoipa requires serde, as it describes the serialized error",
+            ));
+        }
+
+        let name_str = name.to_string();
+        let properties = wire_fields.iter().map(|(key, ty, _)| {
+            // Context pairs are written as two element arrays, which utoipa has no tuple schema for
+            let ty = if key == "context" {
+                quote! { ::std::vec::Vec<::std::vec::Vec<::std::string::String>> }
+            } else {
+                ty.clone()
+            };
+
+            quote! { .property(#key, <#ty as ::utoipa::PartialSchema>::schema()) }
+        });
+        let required = wire_fields
+            .iter()
+            .filter(|(_, _, required)| *required)
+            .map(|(key, _, _)| key);
+
+        quote! {
+            impl ::utoipa::PartialSchema for #name {
+                fn schema() -> ::utoipa::openapi::RefOr<::utoipa::openapi::schema::Schema> {
+                    let object = ::utoipa::openapi::schema::ObjectBuilder::new()
+                        #(#properties)*
+                        #(.required(#required))*
+                        .build();
+
+                    ::utoipa::openapi::RefOr::T(::utoipa::openapi::schema::Schema::Object(object))
+                }
+            }
+
+            impl ::utoipa::ToSchema for #name {
+                fn name() -> ::std::borrow::Cow<'static, str> {
+                    ::std::borrow::Cow::Borrowed(#name_str)
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // Reports the variant metadata to miette, so the error renders as a diagnostic
+    let miette_impl = if attrs.miette {
+        let code = variants
+            .iter()
+            .any(|(_, variant_attrs)| variant_attrs.code.is_some())
+            .then(|| {
+                quote! {
+                    fn code<'a>(&'a self) -> ::core::option::Option<::std::boxed::Box<dyn ::core::fmt::Display + 'a>> {
+                        Some(::std::boxed::Box::new(self.code()))
+                    }
+                }
+            });
+        let help = has_help.then(|| {
+            quote! {
+                fn help<'a>(&'a self) -> ::core::option::Option<::std::boxed::Box<dyn ::core::fmt::Display + 'a>> {
+                    self.help()
+                        .map(|help| ::std::boxed::Box::new(help) as ::std::boxed::Box<dyn ::core::fmt::Display>)
+                }
+            }
+        });
+        let url = has_docs_url.then(|| {
+            quote! {
+                fn url<'a>(&'a self) -> ::core::option::Option<::std::boxed::Box<dyn ::core::fmt::Display + 'a>> {
+                    self.docs_url()
+                        .map(|url| ::std::boxed::Box::new(url) as ::std::boxed::Box<dyn ::core::fmt::Display>)
+                }
+            }
+        });
+        let severity = variants
+            .iter()
+            .any(|(_, variant_attrs)| variant_attrs.severity.is_some())
+            .then(|| {
+                let arms = unskipped(&variants).map(|(variant, variant_attrs)| {
+                    let ident = &variant.ident;
+                    match &variant_attrs.severity {
+                        Some((_, severity)) => {
+                            quote! { #kind_ident::#ident { .. } => Some(::miette::Severity::#severity) }
+                        }
+                        None => quote! { #kind_ident::#ident { .. } => None },
+                    }
+                });
+                let fallback = skipped_fallback(&variants, quote! { None });
+
+                quote! {
+                    fn severity(&self) -> ::core::option::Option<::miette::Severity> {
+                        match &#this_kind {
+                            #(#arms,)*
+                            #fallback
+                        }
+                    }
+                }
+            });
+
+        quote! {
+            impl #impl_generics ::miette::Diagnostic for #name #ty_generics #error_where_clause {
+                #code
+
+                #severity
+
+                #help
+
+                #url
+            }
+        }
+    } else {
+        if let Some((severity, _)) = variants
+            .iter()
+            .find_map(|(_, variant_attrs)| variant_attrs.severity.as_ref())
+        {
+            return Err(syn::Error::new_spanned(
+                severity,
+                "severity requires the miette attribute",
+            ));
+        }
+
+        quote! {}
+    };
+
+    // defmt can't format arbitrary sources, so they go through their core::fmt impls
+    let defmt_impl = if attrs.defmt {
+        let write_source = source_type.as_ref().map(|_| {
+            let adapter = if source_fn {
+                quote! { Display2Format }
+            } else {
+                quote! { Debug2Format }
+            };
+
+            quote! {
+                if let Some(source) = #first_source {
+                    ::defmt::write!(fmt, ": {}", ::defmt::#adapter(source));
+                }
+            }
+        });
+
+        let mut generics = error_generics.clone();
+        generics
+            .make_where_clause()
+            .predicates
+            .push(parse_quote!(#kind_type: ::defmt::Format));
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+        quote! {
+            impl #impl_generics ::defmt::Format for #name #ty_generics #where_clause {
+                fn format(&self, fmt: ::defmt::Formatter<'_>) {
+                    ::defmt::write!(fmt, "{}", &#this_kind);
+                    #write_source
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // Fail the build when the struct outgrows the size budget, e.g. after a kind gains a large field
+    let max_size_assertion = if let Some(max_size) = &attrs.max_size {
+        if !generics.params.is_empty() {
+            return Err(syn::Error::new_spanned(
+                max_size,
+                "max_size can't be combined with a generic kind enum, as its size isn't known",
+            ));
+        }
+
+        let max = max_size.base10_parse::<usize>()?;
+        let message = format!("{} is larger than max_size = {} bytes", name, max);
+        let max = proc_macro2::Literal::usize_unsuffixed(max);
+
+        quote! {
+            const _: () = ::core::assert!(::core::mem::size_of::<#name>() <= #max, #message);
+        }
+    } else {
+        quote! {}
+    };
+
+    // A Debug template or the chain report replaces the derived Debug
+    let plain_debug = if let Some(debug_format) = &attrs.debug {
+        if !attrs.derive_debug {
+            return Err(syn::Error::new_spanned(
+                debug_format,
+                "debug can't be combined with derive_debug = false, as it already replaces the derived Debug",
+            ));
+        }
+
+        Some(quote! {
+            #kind_binding
+            #source_binding
+            write!(f, #debug_format)
+        })
+    } else if attrs.debug_chain {
+        if !attrs.derive_debug {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "debug_chain can't be combined with derive_debug = false, as it replaces the derived Debug",
+            ));
+        }
+
+        // Same output as the derived Debug of the unboxed struct
+        let name_str = name.to_string();
+        let field_names = std::iter::once(kind_field_name.clone())
+            .chain(source_type.as_ref().map(|_| source_field_name.clone()))
+            .chain(extra_fields.iter().map(|(ident, _)| ident.clone()))
+            .collect::<Vec<_>>();
+        let field_strs = field_names.iter().map(|ident| ident.to_string());
+
+        Some(quote! {
+            f.debug_struct(#name_str)
+                #(.field(#field_strs, &#this.#field_names))*
+                .finish()
+        })
+    } else {
+        None
+    };
+    let debug_impl = match plain_debug {
+        Some(plain_debug) if attrs.debug_chain => quote! {
+            impl #impl_generics ::core::fmt::Debug for #name #ty_generics #error_where_clause {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    if !f.alternate() {
+                        #plain_debug
+                    } else {
+                        write!(f, "{}", self)?;
+                        let mut source = ::core::error::Error::source(self);
+                        if source.is_some() {
+                            f.write_str("\n\nCaused by:")?;
+                        }
+                        let mut index = 0;
+                        while let Some(cause) = source {
+                            write!(f, "\n    {}: {}", index, cause)?;
+                            index += 1;
+                            source = cause.source();
+                        }
+                        Ok(())
+                    }
+                }
+            }
+        },
+        Some(plain_debug) => quote! {
+            impl #impl_generics ::core::fmt::Debug for #name #ty_generics #display_where_clause {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    #plain_debug
+                }
+            }
+        },
+        None => quote! {},
+    };
+    let derive_debug = attrs.derive_debug && attrs.debug.is_none() && !attrs.debug_chain;
+    let derive_debug = if derive_debug {
+        quote! { #[derive(::core::fmt::Debug)] }
+    } else {
+        quote! {}
+    };
+    // Extra derives must not clash with the impls generated for the struct
+    for derive in &attrs.derives {
+        let conflict = if derive.is_ident("Debug") && attrs.debug.is_some() {
+            Some("debug")
+        } else if derive.is_ident("Debug") && attrs.debug_chain {
+            Some("debug_chain")
+        } else if derive.is_ident("Debug") && attrs.derive_debug {
+            Some("derive_debug")
+        } else if ["PartialEq", "Eq", "Hash"]
+            .iter()
+            .any(|ident| derive.is_ident(ident))
+            && attrs.cmp_by_kind
+        {
+            Some("cmp_by_kind")
+        } else if ["PartialEq", "Eq", "PartialOrd", "Ord"]
+            .iter()
+            .any(|ident| derive.is_ident(ident))
+            && attrs.ord_by_kind
+        {
+            Some("ord_by_kind")
+        } else if derive.is_ident("Clone") && attrs.io_clone {
+            Some("io_clone")
+        } else if derive.is_ident("Clone") && arc_source {
+            Some("arc_source")
+        } else {
+            None
+        };
+        if let Some(conflict) = conflict {
+            return Err(syn::Error::new_spanned(
+                derive,
+                format!(
+                    "{} is already implemented because of {}",
+                    quote! { #derive },
+                    conflict
+                ),
+            ));
+        }
+    }
+    let derive_debug = if attrs.derives.is_empty() {
+        derive_debug
+    } else {
+        let derives = &attrs.derives;
+        quote! {
+            #derive_debug
+            #[derive(#(#derives),*)]
+        }
+    };
+
+    let struct_attrs = &attrs.struct_attrs;
+    let non_exhaustive = if attrs.non_exhaustive {
+        quote! { #[non_exhaustive] }
+    } else {
+        quote! {}
+    };
+    let fields = quote! {
+        #fields_vis #kind_field_name: #kind_type,
+        #source_field
+        #(#extra_field_defs,)*
+    };
+    let struct_def = if attrs.boxed {
+        quote! {
+            #derive_debug
+            #(#struct_attrs)*
+            #non_exhaustive
+            #must_use
+            #repr_transparent
+            #type_vis struct #name #generics (::std::boxed::Box<#inner_name #ty_generics>) #where_clause;
+
+            #derive_debug
+            struct #inner_name #generics #where_clause {
+                #fields
+            }
+        }
+    } else {
+        quote! {
+            #derive_debug
+            #(#struct_attrs)*
+            #non_exhaustive
+            #must_use
+            #repr_transparent
+            #type_vis struct #name #generics #where_clause {
+                #fields
+            }
+        }
+    };
+
+    // Variant constructors must not clash with the other generated methods
+    let mut generated_fns = impl_fn_names(quote! {
+        #new_fn
+        #from_kind_fn
+        #new_with_message_fn
+        #try_new_fn
+        #source_methods
+        #replace_source_fn
+        #kind_fn
+        #source_lifetime_method
+        #code_methods
+        #(#extra_methods)*
+    });
+    if attrs.display_detail.is_some() {
+        generated_fns.push("detail".to_string());
+    }
+    for (ident, _) in &field_accessors {
+        let accessor = ident.unraw().to_string();
+        if generated_fns.contains(&accessor) {
+            return Err(syn::Error::new_spanned(
+                ident,
+                format!(
+                    "extra field {} clashes with the generated {}() method",
+                    ident, accessor
+                ),
+            ));
+        }
+        generated_fns.push(accessor);
+    }
+    let field_accessors = field_accessors.into_iter().map(|(_, accessor)| accessor);
+    let default_kind_variant = match &attrs.default_kind {
+        Some(syn::Expr::Path(expr_path)) => {
+            expr_path.path.segments.last().map(|segment| &segment.ident)
+        }
+        _ => None,
+    };
+    let mut ctor_names = Vec::with_capacity(variant_ctors.len());
+    let mut variant_ctor_fns = Vec::with_capacity(variant_ctors.len());
+    for VariantCtor {
+        variant,
+        ctor,
+        params,
+        args,
+        body,
+        aliases,
+    } in variant_ctors
+    {
+        // `other()` of a boxed source already builds the default kind, so it stands in for the
+        // constructor of that variant
+        let stand_in = ctor == "other"
+            && generated_fns.contains(&ctor.to_string())
+            && default_kind_variant == Some(&variant.ident)
+            && variant.fields.is_empty();
+
+        // A constructor clashing with another generated method is named by its first alias instead
+        let (ctor, aliases) = if !stand_in && generated_fns.contains(&ctor.unraw().to_string()) {
+            match aliases.split_first() {
+                Some(((lit_str, alias), _)) if generated_fns.contains(&alias.to_string()) => {
+                    return Err(syn::Error::new_spanned(
+                        lit_str,
+                        format!(
+                            "alias `{}` collides with another generated method of the same name",
+                            alias
+                        ),
+                    ));
+                }
+                Some(((_, alias), aliases)) => (alias.clone(), aliases),
+                None => {
+                    return Err(syn::Error::new_spanned(
+                        variant,
+                        format!(
+                            "variant constructor `{}()` clashes with another generated method, name it with #[kind_error(alias = \"...\")] or set variant_ctors = false",
+                            ctor.unraw()
+                        ),
+                    ));
+                }
+            }
+        } else {
+            (ctor, aliases)
+        };
+        let name = ctor.unraw().to_string();
+        if ctor_names.contains(&name) {
+            return Err(syn::Error::new_spanned(
+                variant,
+                format!(
+                    "variant constructor `{}()` is generated for more than one variant",
+                    name
+                ),
+            ));
+        }
+        ctor_names.push(name);
+        if !stand_in {
+            variant_ctor_fns.push(quote! {
+                #ctor_attrs
+                #method_case_attrs
+                #new_vis fn #ctor(#params) -> Self {
+                    #body
+                }
+            });
+        }
+
+        // Old names of a renamed variant keep working, delegating to the new constructor
+        for (lit_str, alias) in aliases {
+            let alias_name = alias.to_string();
+            if generated_fns.contains(&alias_name) || ctor_names.contains(&alias_name) {
+                return Err(syn::Error::new_spanned(
+                    lit_str,
+                    format!(
+                        "alias `{}` collides with another generated method of the same name",
+                        alias_name
+                    ),
+                ));
+            }
+            ctor_names.push(alias_name);
+
+            let note = format!("renamed to `{}`", ctor.unraw());
+            variant_ctor_fns.push(quote! {
+                #[deprecated(note = #note)]
+                #ctor_attrs
+                #method_case_attrs
+                #new_vis fn #alias(#params) -> Self {
+                    Self::#ctor(#args)
+                }
+            });
+        }
+    }
+
+    let expand = quote! {
+        #source_assertion
+
+        #struct_def
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            #new_fn
+
+            #from_kind_fn
+
+            #new_with_message_fn
+
+            #try_new_fn
+
+            #source_methods
+
+            #replace_source_fn
+
+            #kind_fn
+
+            #source_lifetime_method
+
+            #code_methods
+
+            #(#extra_methods)*
+
+            #(#field_accessors)*
+
+            #(#variant_ctor_fns)*
+        }
+
+        #display_impl
+
+        #debug_impl
+
+        #detail_impl
+
+        impl #impl_generics ::core::error::Error for #name #ty_generics #error_where_clause {
+            #source_method
+
+            #legacy_error_methods
+        }
+
+        #termination_impl
+
+        #cmp_eq_impl
+
+        #hash_impl
+
+        #ord_impl
+
+        #eq_kind_impl
+
+        #as_ref_error_impl
+
+        #io_clone_impl
+
+        #max_size_assertion
+
+        #from_impls
+
+        #catch_all_impl
+
+        #from_tuple_impl
+
+        #(#variant_from_impls)*
+
+        #from_kind_impl
+
+        #source_enum_items
+
+        #kind_is_fns_impl
+
+        #builder_items
+
+        #arbitrary_impl
+
+        #serde_impl
+
+        #json_schema_impl
+
+        #utoipa_impl
+
+        #miette_impl
+
+        #defmt_impl
+    };
+
+    Ok(expand.into())
+}
+
+/// Generate `code()` and, for fieldless enums, `from_code()` from the per-variant `code` attributes.
+fn code_methods(
+    attrs: &KindErrorAttrs,
+    variants: &[(&syn::Variant, VariantAttrs)],
+    kind_ident: &Ident,
+    kind_type: &proc_macro2::TokenStream,
+    kind_fn_vis: &Visibility,
+    kind: &proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    if variants
+        .iter()
+        .all(|(_, variant_attrs)| variant_attrs.code.is_none())
+    {
+        if let Some(code_ty) = &attrs.code_ty {
+            return Err(syn::Error::new_spanned(
+                code_ty,
+                "code_ty requires a code attribute on every variant",
+            ));
+        }
+        if let Some(from_code) = &attrs.from_code {
+            return Err(syn::Error::new_spanned(
+                from_code,
+                "from_code requires a code attribute on every variant",
+            ));
+        }
+        if let Some(skip_code) = &attrs.skip_code {
+            return Err(syn::Error::new_spanned(
+                skip_code,
+                "skip_code requires a code attribute on every variant",
+            ));
+        }
+
+        return Ok(quote! {});
+    }
+
+    let code_ty = attrs
+        .code_ty
+        .clone()
+        .unwrap_or_else(|| Ident::new("u32", proc_macro2::Span::call_site()));
+    let max = match code_ty.to_string().as_str() {
+        "u8" => u8::MAX as u64,
+        "u16" => u16::MAX as u64,
+        "u32" => u32::MAX as u64,
+        "u64" => u64::MAX,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &code_ty,
+                "code_ty must be one of u8, u16, u32 or u64",
+            ));
+        }
+    };
+
+    // Skipped variants report a code of their own, which no other variant may reuse
+    let has_skipped = unskipped(variants).count() < variants.len();
+    let skip_code = match &attrs.skip_code {
+        Some(skip_code) if !has_skipped => {
+            return Err(syn::Error::new_spanned(
+                skip_code,
+                "skip_code requires a variant marked skip",
+            ));
+        }
+        Some(skip_code) => {
+            let value = skip_code.base10_parse::<u64>()?;
+            if value > max {
+                return Err(syn::Error::new_spanned(
+                    skip_code,
+                    format!("skip_code {} does not fit in {}", value, code_ty),
+                ));
+            }
+            value
+        }
+        None => 0,
+    };
+
+    let mut codes = Vec::with_capacity(variants.len());
+    for (variant, variant_attrs) in unskipped(variants) {
+        let code = variant_attrs.code.as_ref().ok_or_else(|| {
+            syn::Error::new_spanned(
+                variant,
+                "every variant needs a code attribute when any variant has one",
+            )
+        })?;
+
+        let value = code.base10_parse::<u64>()?;
+        if value > max {
+            return Err(syn::Error::new_spanned(
+                code,
+                format!("code {} does not fit in {}", value, code_ty),
+            ));
+        }
+        if codes.iter().any(|(_, other)| *other == value) {
+            return Err(syn::Error::new_spanned(
+                code,
+                format!("code {} is used by more than one variant", value),
+            ));
+        }
+        if has_skipped && value == skip_code {
+            return Err(syn::Error::new_spanned(
+                code,
+                format!(
+                    "code {} is reported for skipped variants, set skip_code to another code",
+                    value
+                ),
+            ));
+        }
+
+        codes.push((&variant.ident, value));
+    }
+
+    let code_arms = codes.iter().map(|(ident, value)| {
+        let value = proc_macro2::Literal::u64_unsuffixed(*value);
+        quote! { #kind_ident::#ident { .. } => #value }
+    });
+    let skip_code = proc_macro2::Literal::u64_unsuffixed(skip_code);
+    let code_fallback = skipped_fallback(variants, quote! { #skip_code });
+
+    // A variant carrying fields can't be rebuilt from its code alone, so `from_code()` is only
+    // generated for fieldless enums unless explicitly requested
+    let field_variant = unskipped(variants)
+        .map(|(variant, _)| variant)
+        .find(|variant| !matches!(variant.fields, syn::Fields::Unit));
+    let from_code = match (&attrs.from_code, field_variant) {
+        (Some(from_code), Some(variant)) if from_code.value() => {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "from_code requires every variant to be fieldless, as a variant carrying fields can't be rebuilt from its code",
+            ));
+        }
+        (Some(from_code), _) => from_code.value(),
+        (None, field_variant) => field_variant.is_none(),
+    };
+
+    let from_code = if from_code {
+        let from_code_arms = codes.iter().map(|(ident, value)| {
+            let value = proc_macro2::Literal::u64_unsuffixed(*value);
+            quote! { #value => Some(#kind_ident::#ident) }
+        });
+
+        quote! {
+            #kind_fn_vis fn from_code(code: #code_ty) -> Option<#kind_type> {
+                match code {
+                    #(#from_code_arms,)*
+                    _ => None,
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    Ok(quote! {
+        #kind_fn_vis fn code(&self) -> #code_ty {
+            match &#kind {
+                #(#code_arms,)*
+                #code_fallback
+            }
+        }
+
+        #from_code
+    })
+}
+
+/// Match the kind to the string given by a variant attribute, or `None` for variants without one.
+fn variant_str_method(
+    variants: &[(&syn::Variant, VariantAttrs)],
+    kind_ident: &Ident,
+    kind: &proc_macro2::TokenStream,
+    lit_str: impl Fn(&VariantAttrs) -> Option<&syn::LitStr>,
+) -> proc_macro2::TokenStream {
+    let arms = unskipped(variants).map(|(variant, variant_attrs)| {
+        let ident = &variant.ident;
+        match lit_str(variant_attrs) {
+            Some(lit_str) => quote! { #kind_ident::#ident { .. } => Some(#lit_str) },
+            None => quote! { #kind_ident::#ident { .. } => None },
+        }
+    });
+    let fallback = skipped_fallback(variants, quote! { None });
+
+    quote! {
+        match &#kind {
+            #(#arms,)*
+            #fallback
+        }
+    }
+}
+
+/// The variants not marked with `skip`, which get the per-variant methods.
+fn unskipped<'a, 'v>(
+    variants: &'a [(&'v syn::Variant, VariantAttrs)],
+) -> impl Iterator<Item = &'a (&'v syn::Variant, VariantAttrs)> {
+    variants
+        .iter()
+        .filter(|(_, variant_attrs)| variant_attrs.skip.is_none())
+}
+
+/// The `_ => value` arm standing in for skipped variants in a generated match.
+fn skipped_fallback(
+    variants: &[(&syn::Variant, VariantAttrs)],
+    value: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    if variants
+        .iter()
+        .any(|(_, variant_attrs)| variant_attrs.skip.is_some())
+    {
+        quote! {
+            #[allow(unreachable_patterns)]
+            _ => #value,
+        }
+    } else {
+        quote! {}
+    }
+}
+
+/// Whether the type is spelled as `io::Error` or `std::io::Error`.
+fn is_io_error(ty: &Type) -> bool {
+    let Type::Path(type_path) = ty else {
+        return false;
+    };
+    if type_path.qself.is_some() {
+        return false;
+    }
+
+    let segments = type_path
+        .path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<_>>();
+
+    matches!(
+        segments.iter().map(String::as_str).collect::<Vec<_>>()[..],
+        ["io", "Error"] | ["std", "io", "Error"]
+    )
+}
+
+/// Shortcut constructor named after a kind variant, built once the other method names are known.
+struct VariantCtor<'a> {
+    variant: &'a syn::Variant,
+    ctor: Ident,
+    /// Source and variant field parameters.
+    params: proc_macro2::TokenStream,
+    /// The parameters forwarded by an alias.
+    args: proc_macro2::TokenStream,
+    body: proc_macro2::TokenStream,
+    aliases: &'a [(syn::LitStr, Ident)],
+}
+
+/// Internal enum holding the source of an error whose variants don't all share one source type.
+struct SourceEnum<'a> {
+    ident: Ident,
+    type_vis: Visibility,
+    /// Enum variant and the source type it holds, the shared source type comes first.
+    variants: Vec<(Ident, &'a Type)>,
+    first_variant_source: Type,
+}
+
+/// Build the source enum when some variant declares a source type different from the shared one.
+fn variant_source_enum<'a>(
+    variants: &'a [(&syn::Variant, VariantAttrs)],
+    source_type: &'a Type,
+    name: &Ident,
+    type_vis: &Visibility,
+) -> Option<SourceEnum<'a>> {
+    let source_str = quote! { #source_type }.to_string();
+    // The shared slot can't be named after a kind variant, since those name the other slots
+    let mut enum_variants = vec![(format_ident!("__Shared"), source_type)];
+    let mut seen = vec![source_str];
+    let mut first_variant_source = None;
+
+    for (variant, variant_attrs) in variants {
+        let Some(ty) = &variant_attrs.source else {
+            continue;
+        };
+
+        // Variants declaring a source type that is already held reuse its enum variant
+        let ty_str = quote! { #ty }.to_string();
+        if seen.contains(&ty_str) {
+            continue;
+        }
+        first_variant_source.get_or_insert_with(|| ty.clone());
+        seen.push(ty_str);
+        enum_variants.push((variant.ident.clone(), ty));
+    }
+
+    first_variant_source.map(|first_variant_source| SourceEnum {
+        ident: format_ident!("{}Source", name),
+        type_vis: type_vis.clone(),
+        variants: enum_variants,
+        first_variant_source,
+    })
+}
+
+impl SourceEnum<'_> {
+    fn expand(&self) -> proc_macro2::TokenStream {
+        let ident = &self.ident;
+        let type_vis = &self.type_vis;
+        let variant_idents = self
+            .variants
+            .iter()
+            .map(|(variant, _)| variant)
+            .collect::<Vec<_>>();
+        let variant_types = self.variants.iter().map(|(_, ty)| ty);
+
+        let from_impls = self.variants.iter().map(|(variant, ty)| {
+            quote! {
+                impl ::core::convert::From<#ty> for #ident {
+                    fn from(source: #ty) -> Self {
+                        Self::#variant(source)
+                    }
+                }
+            }
+        });
+
+        quote! {
+            #[doc(hidden)]
+            #type_vis enum #ident {
+                #(#variant_idents(#variant_types),)*
+            }
+
+            impl #ident {
+                fn as_error(&self) -> &(dyn ::core::error::Error + 'static) {
+                    match self {
+                        #(Self::#variant_idents(source) => source,)*
+                    }
+                }
+
+                #[allow(dead_code)]
+                fn as_error_mut(&mut self) -> &mut (dyn ::core::error::Error + 'static) {
+                    match self {
+                        #(Self::#variant_idents(source) => source,)*
+                    }
+                }
+            }
+
+            #(#from_impls)*
+
+            impl ::core::fmt::Debug for #ident {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    ::core::fmt::Debug::fmt(self.as_error(), f)
+                }
+            }
+
+            impl ::core::fmt::Display for #ident {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    ::core::fmt::Display::fmt(self.as_error(), f)
+                }
+            }
+        }
+    }
+}
+
+/// A method of the source forwarded by the error, e.g. `pub fn kind(&self) -> io::ErrorKind as io_kind`.
+struct DelegateFn {
+    vis: Visibility,
+    sig: syn::Signature,
+    rename: Option<Ident>,
+}
+
+impl Parse for DelegateFn {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let vis = input.parse()?;
+        // The macro can't look up the signature of a source method from its name alone
+        if input.peek(Ident) {
+            let name = input.parse::<Ident>()?;
+            return Err(syn::Error::new(
+                name.span(),
+                format!(
+                    "delegate needs the full signature of `{}`, e.g. \"pub fn {}(&self) -> ...\", as the methods of the source type aren't visible to the macro",
+                    name, name
+                ),
+            ));
+        }
+        let sig = input.parse::<syn::Signature>()?;
+        let rename = if input.peek(Token![as]) {
+            input.parse::<Token![as]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+
+        Ok(Self { vis, sig, rename })
+    }
+}
+
+/// Internal layout of the generated struct.
+#[derive(Clone, Copy)]
+enum Repr {
+    /// The kind is stored inline and the source is boxed only when present, like `io::Error`.
+    Compact,
+}
+
+impl Repr {
+    fn from_lit(lit_str: &syn::LitStr) -> syn::Result<Self> {
+        match lit_str.value().as_str() {
+            "compact" => Ok(Self::Compact),
+            _ => Err(syn::Error::new_spanned(lit_str, "repr must be compact")),
+        }
+    }
+}
+
+/// When the backtrace of a new error is captured.
+enum BacktracePolicy {
+    /// Always capture, regardless of the environment.
+    Always,
+    /// Follow `RUST_LIB_BACKTRACE` and `RUST_BACKTRACE`, like `Backtrace::capture()`.
+    Env,
+    /// Capture only when the named environment variable is set to something other than `0`.
+    EnvVar(String),
+}
+
+impl BacktracePolicy {
+    fn from_lit(lit_str: &syn::LitStr) -> syn::Result<Self> {
+        let value = lit_str.value();
+
+        match value.as_str() {
+            "always" => Ok(Self::Always),
+            "env" => Ok(Self::Env),
+            _ => match value.strip_prefix("env:") {
+                Some(var) if !var.is_empty() => Ok(Self::EnvVar(var.to_string())),
+                _ => Err(syn::Error::new_spanned(
+                    lit_str,
+                    "backtrace_policy must be always, env or env:VAR",
+                )),
+            },
+        }
+    }
+}
+
+/// Case convention applied to variant names, mirroring serde's `rename_all`.
+#[derive(Clone, Copy)]
+enum RenameRule {
+    Snake,
+    Camel,
+    ScreamingSnake,
+}
+
+impl RenameRule {
+    fn from_lit(lit_str: &syn::LitStr) -> syn::Result<Self> {
+        match lit_str.value().as_str() {
+            "snake_case" => Ok(Self::Snake),
+            "camelCase" => Ok(Self::Camel),
+            "SCREAMING_SNAKE_CASE" => Ok(Self::ScreamingSnake),
+            _ => Err(syn::Error::new_spanned(
+                lit_str,
+                "rename_all must be one of snake_case, camelCase or SCREAMING_SNAKE_CASE",
+            )),
+        }
+    }
+
+    /// Name of a method generated for a variant, e.g. `is_not_found` for `Is` and `NotFound`.
+    /// Keywords are escaped as raw identifiers, and the few that can't be are suffixed instead.
+    fn method(self, prefix: &str, variant: &Ident) -> Ident {
+        let name = self.apply(&format!("{}{}", prefix, variant.unraw()));
+
+        if syn::parse_str::<Ident>(&name).is_ok() {
+            Ident::new(&name, variant.span())
+        } else if matches!(name.as_str(), "crate" | "self" | "super" | "Self") {
+            format_ident!("{}_", name, span = variant.span())
+        } else {
+            Ident::new_raw(&name, variant.span())
+        }
+    }
+
+    fn apply(self, variant: &str) -> String {
+        let words = split_words(variant);
+
+        match self {
+            Self::Snake => words.join("_"),
+            Self::ScreamingSnake => words.join("_").to_uppercase(),
+            Self::Camel => words
+                .iter()
+                .enumerate()
+                .map(|(i, word)| {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) if i > 0 => first.to_uppercase().chain(chars).collect(),
+                        _ => word.clone(),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Split a `PascalCase` identifier into lowercase words, keeping acronyms like `HTTP` together.
+fn split_words(ident: &str) -> Vec<String> {
+    let chars = ident.chars().collect::<Vec<_>>();
+    let mut words = Vec::new();
+    let mut word = String::new();
+
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '_' {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+
+        let prev = i.checked_sub(1).map(|i| chars[i]);
+        let next = chars.get(i + 1);
+        let boundary = c.is_uppercase()
+            && match prev {
+                Some(prev) if prev.is_lowercase() || prev.is_ascii_digit() => true,
+                Some(prev) if prev.is_uppercase() => next.is_some_and(|next| next.is_lowercase()),
+                _ => false,
+            };
+        if boundary && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+
+        word.extend(c.to_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+/// Whether the type is a boxed trait object, e.g. `Box<dyn Error + Send + Sync>`.
+fn is_boxed_trait_object(ty: &Type) -> bool {
+    let Type::Path(type_path) = ty else {
+        return false;
+    };
+    let Some(segment) = type_path.path.segments.last() else {
+        return false;
+    };
+    if segment.ident != "Box" {
+        return false;
+    }
+
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(arguments) => matches!(
+            arguments.args.first(),
+            Some(syn::GenericArgument::Type(Type::TraitObject(_)))
+        ),
+        _ => false,
+    }
+}
+
+/// Whether the type is a boxed `dyn Error` trait object, which any `Error + Send + Sync` converts into.
+fn is_boxed_dyn_error(ty: &Type) -> bool {
+    if !is_boxed_trait_object(ty) {
+        return false;
+    }
+    let Type::Path(type_path) = ty else {
+        return false;
+    };
+    let Some(syn::PathArguments::AngleBracketed(arguments)) = type_path
+        .path
+        .segments
+        .last()
+        .map(|segment| &segment.arguments)
+    else {
+        return false;
+    };
+    let Some(syn::GenericArgument::Type(Type::TraitObject(trait_object))) = arguments.args.first()
+    else {
+        return false;
+    };
+
+    trait_object.bounds.iter().any(|bound| {
+        matches!(bound, syn::TypeParamBound::Trait(trait_bound)
+            if trait_bound.path.segments.last().is_some_and(|segment| segment.ident == "Error"))
+    })
+}
+
+fn kind_error_defaults_impl(
+    defaults: &Punctuated<syn::MetaNameValue, Token![,]>,
+    module: &mut syn::ItemMod,
+) -> syn::Result<()> {
+    // Reject unknown keys and malformed values once, instead of in every derive
+    syn::parse2::<KindErrorAttrs>(quote! { #defaults })?;
+
+    let Some((_, items)) = &mut module.content else {
+        return Err(syn::Error::new_spanned(
+            &module.ident,
+            "kind_error_defaults requires an inline module",
+        ));
+    };
+
+    for item in items {
+        match item {
+            syn::Item::Enum(item_enum) if derives_kind_error(&item_enum.attrs) => {
+                merge_kind_error_defaults(defaults, &mut item_enum.attrs)?;
+            }
+            syn::Item::Mod(nested) if nested.content.is_some() => {
+                kind_error_defaults_impl(defaults, nested)?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn derives_kind_error(attrs: &[Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("derive"))
+        .any(|attr| {
+            attr.parse_args_with(Punctuated::<syn::Path, Token![,]>::parse_terminated)
+                .is_ok_and(|paths| {
+                    paths.iter().any(|path| {
+                        path.segments
+                            .last()
+                            .is_some_and(|segment| segment.ident == "KindError")
+                    })
+                })
+        })
+}
+
+/// Prepend the defaults not overridden by the enum to its `#[kind_error(...)]` attribute.
+fn merge_kind_error_defaults(
+    defaults: &Punctuated<syn::MetaNameValue, Token![,]>,
+    attrs: &mut Vec<Attribute>,
+) -> syn::Result<()> {
+    let existing = attrs
+        .iter()
+        .position(|attr| attr.path().is_ident("kind_error"));
+    let local = match existing {
+        Some(index) => attrs[index]
+            .parse_args_with(Punctuated::<syn::MetaNameValue, Token![,]>::parse_terminated)?,
+        None => Punctuated::new(),
+    };
+
+    let merged = defaults
+        .iter()
+        .filter(|default| {
+            !local
+                .iter()
+                .any(|local| local.path.get_ident() == default.path.get_ident())
+        })
+        .chain(&local);
+    let attr: Attribute = parse_quote! { #[kind_error(#(#merged),*)] };
+
+    match existing {
+        Some(index) => attrs[index] = attr,
+        None => attrs.push(attr),
+    }
+
+    Ok(())
+}
+
+/// Implement `arbitrary::Arbitrary` by picking a variant uniformly and filling its fields.
+fn arbitrary_impl(
+    variants: &[(&syn::Variant, VariantAttrs)],
+    kind_ident: &Ident,
+    name: &Ident,
+    source_type: Option<proc_macro2::TokenStream>,
+    new_ident: &Ident,
+    default_fields: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let variant_count = unskipped(variants).count();
+    let source_type = source_type.as_slice();
+    let source_args = source_type.iter().map(|_| quote! { source });
+    let kind_arms = unskipped(variants)
+        .enumerate()
+        .map(|(index, (variant, _))| {
+            let ident = &variant.ident;
+            let kind = match &variant.fields {
+                syn::Fields::Unit => quote! { #kind_ident::#ident },
+                syn::Fields::Unnamed(fields) => {
+                    let values = fields
+                        .unnamed
+                        .iter()
+                        .map(|_| quote! { ::arbitrary::Arbitrary::arbitrary(u)? });
+                    quote! { #kind_ident::#ident(#(#values),*) }
+                }
+                syn::Fields::Named(fields) => {
+                    let values = fields.named.iter().map(|field| {
+                        let field_ident = &field.ident;
+                        quote! { #field_ident: ::arbitrary::Arbitrary::arbitrary(u)? }
+                    });
+                    quote! { #kind_ident::#ident { #(#values),* } }
+                }
+            };
+
+            quote! { #index => #kind }
+        });
+
+    quote! {
+        impl<'arbitrary> ::arbitrary::Arbitrary<'arbitrary> for #name {
+            fn arbitrary(u: &mut ::arbitrary::Unstructured<'arbitrary>) -> ::arbitrary::Result<Self> {
+                let kind = match u.choose_index(#variant_count)? {
+                    #(#kind_arms,)*
+                    _ => unreachable!(),
+                };
+                #(let source: #source_type = ::arbitrary::Arbitrary::arbitrary(u)?;)*
+
+                Ok(Self::#new_ident(kind #(, #source_args)* #default_fields))
+            }
+        }
+    }
+}
+
+/// Names of the methods among generated inherent impl items.
+fn impl_fn_names(items: proc_macro2::TokenStream) -> Vec<String> {
+    let item_impl: syn::ItemImpl = parse_quote! {
+        impl Generated {
+            #items
+        }
+    };
+
+    item_impl
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            syn::ImplItem::Fn(method) => Some(method.sig.ident.unraw().to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The source type taken by `new()`, a `Vec` in sources mode and an `Option` in optional_source mode.
+fn new_param_type(
+    attrs: &KindErrorAttrs,
+    source_type: Option<&Type>,
+) -> Option<proc_macro2::TokenStream> {
+    let source_type = source_type?;
+
+    Some(if attrs.sources {
+        quote! { ::std::vec::Vec<#source_type> }
+    } else if attrs.optional_source || matches!(attrs.repr, Some(Repr::Compact)) {
+        quote! { ::core::option::Option<#source_type> }
+    } else {
+        quote! { #source_type }
+    })
 }
@@ -202,3 +202,2720 @@ fn test_into() {
     let err = IntoKindError::new(IntoKind::First, "err");
     assert_eq!(err.origin(), "err");
 }
+
+// Test borrowed (non-'static) source with source_lifetime
+#[derive(Debug)]
+struct BorrowedError<'a> {
+    msg: &'a str,
+}
+
+impl Display for BorrowedError<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.msg)
+    }
+}
+
+impl std::error::Error for BorrowedError<'_> {}
+
+#[derive(KindError, Debug, Eq, PartialEq)]
+#[kind_error(
+    source = "BorrowedError<'a>",
+    name = "BorrowedSourceError",
+    source_lifetime = "'a"
+)]
+enum BorrowedSourceKind<'a> {
+    Parse(&'a str),
+}
+
+#[test]
+fn test_source_lifetime() {
+    let input = String::from("bad input");
+    let err = BorrowedSourceError::new(
+        BorrowedSourceKind::Parse(&input),
+        BorrowedError { msg: &input },
+    );
+    assert_eq!(*err.kind(), BorrowedSourceKind::Parse("bad input"));
+    assert_eq!(err.source().unwrap().to_string(), "bad input");
+}
+
+mod restricted_borrowed {
+    use kinderror::KindError;
+
+    use super::BorrowedError;
+
+    #[derive(KindError, Debug)]
+    #[kind_error(
+        source = "BorrowedError<'a>",
+        name = "RestrictedBorrowedError",
+        type_vis = "pub",
+        new_vis = "pub",
+        source_lifetime = "'a",
+        origin_fn_vis = ""
+    )]
+    pub enum RestrictedBorrowedKind<'a> {
+        Parse(&'a str),
+    }
+
+    pub fn source_message(err: &RestrictedBorrowedError<'_>) -> String {
+        err.source().unwrap().to_string()
+    }
+}
+
+// No `Error` import here, so `err.source()` can only resolve to the inherent method or the
+// fallback trait
+mod restricted_borrowed_caller {
+    use super::BorrowedError;
+    use super::restricted_borrowed::{
+        RestrictedBorrowedError, RestrictedBorrowedKind, source_message,
+    };
+
+    // Only picked by method resolution when the inherent `source()` isn't visible
+    trait FallbackSource {
+        fn source(&self) -> &'static str {
+            "fallback"
+        }
+    }
+
+    impl FallbackSource for RestrictedBorrowedError<'_> {}
+
+    #[test]
+    fn test_source_lifetime_vis() {
+        let input = String::from("bad input");
+        let err = RestrictedBorrowedError::new(
+            RestrictedBorrowedKind::Parse(&input),
+            BorrowedError { msg: &input },
+        );
+        assert_eq!(source_message(&err), "bad input");
+        assert_eq!(err.source(), "fallback");
+    }
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(source = "io::Error", name = "TerminationError", termination = true)]
+enum TerminationKind {
+    Fatal,
+}
+
+#[test]
+fn test_termination() {
+    use std::process::{ExitCode, Termination};
+
+    let err = TerminationError::new(TerminationKind::Fatal, io::Error::other("fatal"));
+    assert_eq!(err.report(), ExitCode::FAILURE);
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(source = "S", name = "GenericTerminationError", termination = true)]
+enum GenericTerminationKind<S> {
+    Fatal,
+    #[allow(dead_code)]
+    Marker(std::marker::PhantomData<S>),
+}
+
+#[test]
+fn test_termination_generic_source() {
+    use std::process::{ExitCode, Termination};
+
+    let err = GenericTerminationError::<io::Error>::new(
+        GenericTerminationKind::Fatal,
+        io::Error::other("fatal"),
+    );
+    assert_eq!(err.report(), ExitCode::FAILURE);
+}
+
+#[derive(KindError, Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[kind_error(source = "io::Error", name = "OrdError", ord_by_kind = true)]
+enum OrdKind {
+    Warning,
+    Fatal,
+}
+
+#[test]
+fn test_ord_by_kind() {
+    let mut errors = [
+        OrdError::new(OrdKind::Fatal, io::Error::other("fatal")),
+        OrdError::new(OrdKind::Warning, io::Error::other("warning")),
+    ];
+    errors.sort();
+    assert_eq!(*errors[0].kind(), OrdKind::Warning);
+    assert_eq!(*errors.iter().max().unwrap().kind(), OrdKind::Fatal);
+    assert_eq!(
+        OrdError::new(OrdKind::Fatal, io::Error::other("a")),
+        OrdError::new(OrdKind::Fatal, io::Error::other("b"))
+    );
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(
+    source = "io::Error",
+    name = "BoundedError",
+    error_bounds = "io::Error: Send + Sync, BoundedKind: Send"
+)]
+enum BoundedKind {
+    First,
+}
+
+#[test]
+fn test_error_bounds() {
+    fn assert_error<E: std::error::Error + Send + Sync>(_: &E) {}
+
+    let err = BoundedError::new(BoundedKind::First, io::Error::other("err"));
+    assert_error(&err);
+}
+
+#[derive(KindError, Debug, Eq, PartialEq)]
+#[kind_error(
+    source = "io::Error",
+    name = "AggregateError",
+    sources = true,
+    display = "{kind:?} failed with {source} errors"
+)]
+enum AggregateKind {
+    Batch,
+}
+
+#[test]
+fn test_sources() {
+    let mut err = AggregateError::new(AggregateKind::Batch, vec![io::Error::other("first")]);
+    err.push_source(io::Error::other("second"));
+    assert_eq!(err.sources().len(), 2);
+    assert_eq!(err.source().unwrap().to_string(), "first");
+    assert_eq!(err.to_string(), "Batch failed with 2 errors");
+
+    let empty = AggregateError::new(AggregateKind::Batch, vec![]);
+    assert!(empty.source().is_none());
+}
+
+#[derive(KindError, Debug)]
+#[cfg(not(test))]
+#[kind_error(source = "io::Error", name = "DisabledError")]
+enum DisabledKind {
+    First,
+}
+
+// Would clash with the struct generated for `DisabledKind` if the derive ignored its cfg
+struct DisabledError;
+
+#[derive(KindError, Debug, Eq, PartialEq)]
+#[cfg(test)]
+#[kind_error(source = "io::Error", name = "EnabledError")]
+enum EnabledKind {
+    First,
+}
+
+#[test]
+fn test_cfg_kind() {
+    let err = EnabledError::new(EnabledKind::First, io::Error::other("err"));
+    assert_eq!(*err.kind(), EnabledKind::First);
+
+    let _ = DisabledError;
+}
+
+#[derive(KindError, Debug, Eq, PartialEq)]
+#[kind_error(source = "io::Error", name = "CodeError", code_ty = "u16")]
+enum CodeKind {
+    #[kind_error(code = 404)]
+    NotFound,
+    #[kind_error(code = 500)]
+    Internal,
+}
+
+#[test]
+fn test_code() {
+    let err = CodeError::new(CodeKind::NotFound, io::Error::other("err"));
+    let code: u16 = err.code();
+    assert_eq!(code, 404);
+    assert_eq!(CodeError::from_code(500), Some(CodeKind::Internal));
+    assert_eq!(CodeError::from_code(1), None);
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(source = "io::Error", name = "FieldCodeError")]
+enum FieldCodeKind {
+    #[kind_error(code = 1)]
+    WithField(String),
+    #[kind_error(code = 2)]
+    Plain,
+}
+
+#[test]
+fn test_code_with_fields() {
+    let err = FieldCodeError::new(
+        FieldCodeKind::WithField("x".to_string()),
+        io::Error::other("err"),
+    );
+    let code: u32 = err.code();
+    assert_eq!(code, 1);
+}
+
+#[derive(KindError, Debug, Eq, PartialEq, Clone, Copy)]
+#[kind_error(source = "io::Error", name = "RoundTripError", from_code = true)]
+enum RoundTripKind {
+    #[kind_error(code = 10)]
+    Timeout,
+    #[kind_error(code = 20)]
+    Refused,
+}
+
+#[test]
+fn test_from_code_round_trip() {
+    for kind in [RoundTripKind::Timeout, RoundTripKind::Refused] {
+        let err = RoundTripError::new(kind, io::Error::other("err"));
+        assert_eq!(RoundTripError::from_code(err.code()), Some(kind));
+    }
+}
+
+#[derive(KindError, Debug, Clone, Copy, PartialEq)]
+#[kind_error(source = "io::Error", name = "ShardError")]
+enum ShardKind<const SHARD: usize> {
+    #[kind_error(code = 1)]
+    Full,
+    #[kind_error(code = 2)]
+    Offline,
+}
+
+#[test]
+fn test_from_code_generic_kind() {
+    assert_eq!(ShardError::<3>::from_code(2), Some(ShardKind::<3>::Offline));
+    assert_eq!(ShardError::<3>::from_code(3), None);
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(source = "S", name = "GenericSourceError")]
+enum GenericSourceKind<S> {
+    First,
+    #[allow(dead_code)]
+    Marker(std::marker::PhantomData<S>),
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(source = "S", source_fn = false, name = "GenericPlainError")]
+enum GenericPlainKind<S> {
+    First,
+    #[allow(dead_code)]
+    Marker(std::marker::PhantomData<S>),
+}
+
+#[test]
+fn test_generic_source() {
+    let err =
+        GenericSourceError::<io::Error>::new(GenericSourceKind::First, io::Error::other("err"));
+    assert_eq!(err.source().unwrap().to_string(), "err");
+
+    let err = GenericPlainError::<CustomError>::new(GenericPlainKind::First, CustomError);
+    assert!(err.source().is_none());
+    assert!(err.to_string().contains("CustomError"));
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(source = "io::Error", name = "TimestampError", timestamp = true)]
+enum TimestampKind {
+    First,
+}
+
+#[test]
+fn test_timestamp() {
+    let before = std::time::Instant::now();
+    let err = TimestampError::new(TimestampKind::First, io::Error::other("err"));
+    assert!(err.created_at() >= before);
+    assert!(err.elapsed() <= before.elapsed());
+}
+
+#[derive(Debug, Eq, PartialEq)]
+struct ErrorReport {
+    code: u32,
+    message: String,
+}
+
+impl ErrorReport {
+    fn from_parts(code: u32, message: String) -> Self {
+        Self { code, message }
+    }
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(
+    source = "io::Error",
+    name = "ReportError",
+    display = "{kind:?}: {source}",
+    report_fn = "ErrorReport::from_parts"
+)]
+enum ReportKind {
+    #[kind_error(code = 7)]
+    Denied,
+}
+
+#[test]
+fn test_report_fn() {
+    let err = ReportError::new(ReportKind::Denied, io::Error::other("no access"));
+    assert_eq!(
+        err.to_report(),
+        ErrorReport {
+            code: 7,
+            message: "Denied: no access".to_string(),
+        }
+    );
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(source = "io::Error", name = "RedactedError", derive_debug = false)]
+enum RedactedKind {
+    Secret,
+}
+
+impl Debug for RedactedError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedactedError")
+            .field("kind", self.kind())
+            .finish_non_exhaustive()
+    }
+}
+
+#[test]
+fn test_derive_debug_false() {
+    let err = RedactedError::new(RedactedKind::Secret, io::Error::other("password"));
+    assert_eq!(format!("{:?}", err), "RedactedError { kind: Secret, .. }");
+    assert!(err.to_string().contains("password"));
+}
+
+// Test a generated error used as the source of another generated error
+#[derive(KindError, Debug, Eq, PartialEq)]
+#[kind_error(
+    source = "io::Error",
+    name = "InnerError",
+    display = "inner {kind:?}: {source}"
+)]
+enum InnerKind {
+    Read,
+}
+
+#[derive(KindError, Debug, Eq, PartialEq)]
+#[kind_error(
+    source = "InnerError",
+    name = "OuterError",
+    display = "outer {kind:?}: {source}"
+)]
+enum OuterKind {
+    Load,
+}
+
+#[derive(KindError, Debug, Eq, PartialEq)]
+#[kind_error(
+    source = "CustomErrorWrapper",
+    name = "OuterOpaqueError",
+    display = "outer opaque {kind:?}"
+)]
+enum OuterOpaqueKind {
+    Load,
+}
+
+#[test]
+fn test_nested_source_chain() {
+    let inner = InnerError::new(InnerKind::Read, io::Error::other("disk"));
+    let err = OuterError::new(OuterKind::Load, inner);
+    assert_eq!(err.to_string(), "outer Load: inner Read: disk");
+
+    let first = err.source().unwrap();
+    assert_eq!(
+        first.downcast_ref::<InnerError>().unwrap().kind(),
+        &InnerKind::Read
+    );
+    let second = first.source().unwrap();
+    assert!(second.downcast_ref::<io::Error>().is_some());
+    assert!(second.source().is_none());
+}
+
+#[test]
+fn test_nested_source_chain_terminates() {
+    let inner = CustomErrorWrapper::new(CustomErrorKind::Kind2, CustomError);
+    let err = OuterOpaqueError::new(OuterOpaqueKind::Load, inner);
+
+    let first = err.source().unwrap();
+    assert_eq!(first.to_string(), "error kind: Kind2, source: CustomError");
+    assert!(first.source().is_none());
+}
+
+#[derive(KindError, Debug, Eq, PartialEq)]
+#[kind_error(
+    source = "CustomError",
+    source_fn = false,
+    name = "ConstError",
+    const_new = true
+)]
+enum ConstKind {
+    Overflow,
+}
+
+const OVERFLOW: ConstError = ConstError::new(ConstKind::Overflow, CustomError);
+
+#[test]
+fn test_const_new() {
+    assert_eq!(*OVERFLOW.kind(), ConstKind::Overflow);
+}
+
+#[derive(KindError, Debug, Clone, Eq, PartialEq)]
+#[kind_error(source = "std::io::Error", name = "IoCloneError", io_clone = true)]
+enum IoCloneKind {
+    Read,
+}
+
+#[test]
+fn test_io_clone() {
+    let err = IoCloneError::new(
+        IoCloneKind::Read,
+        io::Error::new(io::ErrorKind::NotFound, "missing"),
+    );
+    let cloned = err.clone();
+    assert_eq!(cloned.kind(), err.kind());
+    assert_eq!(cloned.origin().kind(), io::ErrorKind::NotFound);
+    assert_eq!(cloned.origin().to_string(), "missing");
+}
+
+fn check_message(kind: &ValidatedKind, source: &str) {
+    if let ValidatedKind::Described = kind {
+        assert!(!source.is_empty(), "Described errors need a message");
+    }
+}
+
+fn try_check_message(kind: &ValidatedKind, source: &str) -> Result<(), &'static str> {
+    match kind {
+        ValidatedKind::Described if source.is_empty() => Err("empty message"),
+        _ => Ok(()),
+    }
+}
+
+#[derive(KindError, Debug, Eq, PartialEq)]
+#[kind_error(
+    source = "String",
+    source_fn = false,
+    name = "ValidatedError",
+    validate_fn = "check_message",
+    validate_try_fn = "try_check_message",
+    validate_error = "&'static str"
+)]
+enum ValidatedKind {
+    Described,
+    Bare,
+}
+
+#[test]
+fn test_validate() {
+    let err = ValidatedError::new(ValidatedKind::Described, "message");
+    assert_eq!(err.origin(), "message");
+    assert!(ValidatedError::try_new(ValidatedKind::Bare, "").is_ok());
+    assert_eq!(
+        ValidatedError::try_new(ValidatedKind::Described, "").unwrap_err(),
+        "empty message"
+    );
+}
+
+#[test]
+#[should_panic(expected = "Described errors need a message")]
+fn test_validate_panics() {
+    ValidatedError::new(ValidatedKind::Described, "");
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(source = "io::Error", name = "DiscriminantError", discriminant = true)]
+enum DiscriminantKind {
+    Parse { line: usize },
+    Io,
+}
+
+#[test]
+fn test_discriminant() {
+    let first = DiscriminantError::new(DiscriminantKind::Parse { line: 1 }, io::Error::other("a"));
+    let second = DiscriminantError::new(DiscriminantKind::Parse { line: 2 }, io::Error::other("b"));
+    let third = DiscriminantError::new(DiscriminantKind::Io, io::Error::other("c"));
+    assert_eq!(first.kind_discriminant(), second.kind_discriminant());
+    assert_ne!(first.kind_discriminant(), third.kind_discriminant());
+}
+
+#[derive(KindError, Debug, Eq, PartialEq)]
+#[kind_error(
+    source = "Box<dyn std::error::Error + Send + Sync>",
+    source_fn = false,
+    name = "BoxedFromError",
+    default_kind = "BoxedFromKind::Other",
+    from_sources = "io::Error, std::num::ParseIntError"
+)]
+enum BoxedFromKind {
+    Other,
+}
+
+// The same conversions with `Error::source()` reporting the boxed error
+#[derive(KindError, Debug, Eq, PartialEq)]
+#[kind_error(
+    source = "Box<dyn std::error::Error + Send + Sync>",
+    name = "BoxedFromSourceError",
+    default_kind = "BoxedFromSourceKind::Other",
+    from_sources = "io::Error, std::num::ParseIntError"
+)]
+enum BoxedFromSourceKind {
+    Other,
+}
+
+#[test]
+fn test_from_sources() {
+    fn parse(input: &str) -> Result<i32, BoxedFromError> {
+        Ok(input.parse::<i32>()?)
+    }
+
+    fn read() -> Result<(), BoxedFromError> {
+        let result: Result<(), io::Error> = Err(io::Error::other("read failed"));
+        Ok(result?)
+    }
+
+    let err = parse("nan").unwrap_err();
+    assert_eq!(*err.kind(), BoxedFromKind::Other);
+    assert!(err.origin().is::<std::num::ParseIntError>());
+    assert!(read().unwrap_err().origin().is::<io::Error>());
+    assert!(read().unwrap_err().source().is_none());
+}
+
+#[test]
+fn test_from_sources_boxed_source() {
+    fn parse(input: &str) -> Result<i32, BoxedFromSourceError> {
+        Ok(input.parse::<i32>()?)
+    }
+
+    let err = parse("nan").unwrap_err();
+    assert_eq!(*err.kind(), BoxedFromSourceKind::Other);
+    assert!(err.origin().is::<std::num::ParseIntError>());
+    assert!(err.source().unwrap().is::<std::num::ParseIntError>());
+
+    let err = BoxedFromSourceError::from(io::Error::other("read failed"));
+    assert_eq!(err.source().unwrap().to_string(), "read failed");
+}
+
+static INSPECTED: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+fn count_error(_kind: &InspectedKind, _source: &io::Error) {
+    INSPECTED.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+}
+
+#[derive(KindError, Debug, Eq, PartialEq)]
+#[kind_error(
+    source = "io::Error",
+    name = "InspectedError",
+    inspect_fn = "count_error",
+    default_kind = "InspectedKind::Io",
+    from_sources = "io::Error"
+)]
+enum InspectedKind {
+    Io,
+}
+
+#[test]
+fn test_inspect_fn() {
+    let before = INSPECTED.load(std::sync::atomic::Ordering::SeqCst);
+    let err = InspectedError::new(InspectedKind::Io, io::Error::other("err"));
+    assert_eq!(*err.kind(), InspectedKind::Io);
+    let _ = InspectedError::from(io::Error::other("err"));
+    assert_eq!(
+        INSPECTED.load(std::sync::atomic::Ordering::SeqCst),
+        before + 2
+    );
+}
+
+#[derive(KindError, Debug, Eq, PartialEq)]
+#[kind_error(source = "io::Error", name = "ListedError", all_kinds = true)]
+enum ListedKind {
+    First,
+    Second,
+    Third,
+}
+
+#[test]
+fn test_all_kinds() {
+    assert_eq!(
+        ListedError::all_kinds(),
+        &[ListedKind::First, ListedKind::Second, ListedKind::Third]
+    );
+}
+
+#[derive(KindError, Debug, Clone, Copy, Eq, PartialEq)]
+#[kind_error(
+    source = "io::Error",
+    name = "PublicError",
+    is_fns = true,
+    as_fns = true,
+    all_kinds = true
+)]
+enum PublicKind {
+    #[kind_error(code = 404, help = "check the path")]
+    NotFound,
+    #[kind_error(code = 429)]
+    Throttled,
+    #[kind_error(skip)]
+    Poisoned(u8),
+}
+
+#[test]
+fn test_skip() {
+    assert_eq!(
+        PublicError::all_kinds(),
+        &[PublicKind::NotFound, PublicKind::Throttled]
+    );
+    assert_eq!(PublicError::from_code(429), Some(PublicKind::Throttled));
+
+    let err = PublicError::not_found(io::Error::other("gone"));
+    assert!(err.is_not_found());
+    assert_eq!(err.code(), 404);
+    assert_eq!(err.help(), Some("check the path"));
+
+    let err = PublicError::new(PublicKind::Poisoned(1), io::Error::other("poisoned"));
+    assert!(!err.is_not_found());
+    assert!(!err.is_throttled());
+    assert_eq!(err.code(), 0);
+    assert_eq!(err.help(), None);
+}
+
+#[derive(KindError, Debug, Clone, Copy, Eq, PartialEq)]
+#[kind_error(source = "io::Error", name = "SkipCodeError", skip_code = 999)]
+enum SkipCodeKind {
+    #[kind_error(code = 0)]
+    Ok,
+    #[kind_error(code = 1)]
+    Failed,
+    #[kind_error(skip)]
+    Internal,
+}
+
+#[test]
+fn test_skip_code() {
+    let ok = SkipCodeError::new(SkipCodeKind::Ok, io::Error::other("ok"));
+    assert_eq!(ok.code(), 0);
+    assert_eq!(SkipCodeError::from_code(0), Some(SkipCodeKind::Ok));
+
+    let internal = SkipCodeError::new(SkipCodeKind::Internal, io::Error::other("internal"));
+    assert_eq!(internal.code(), 999);
+    assert_eq!(SkipCodeError::from_code(999), None);
+}
+
+#[derive(KindError, Debug, Eq, PartialEq)]
+#[kind_error(
+    source = "io::Error",
+    name = "MixedSourceError",
+    display = "{kind:?}: {source}"
+)]
+enum MixedSourceKind {
+    Read,
+    #[kind_error(source = "std::num::ParseIntError")]
+    Parse,
+}
+
+#[test]
+fn test_variant_source() {
+    let read = MixedSourceError::new(MixedSourceKind::Read, io::Error::other("disk"));
+    assert!(read.origin().is::<io::Error>());
+    assert_eq!(read.to_string(), "Read: disk");
+
+    let parse_err = "x".parse::<u8>().unwrap_err();
+    let parse = MixedSourceError::new(MixedSourceKind::Parse, parse_err.clone());
+    assert!(parse.origin().is::<std::num::ParseIntError>());
+    assert_eq!(parse.source().unwrap().to_string(), parse_err.to_string());
+}
+
+#[derive(KindError, Debug, Eq, PartialEq)]
+#[kind_error(source = "io::Error", name = "DefaultSourceError")]
+enum DefaultSourceKind {
+    Read,
+    #[kind_error(source = "std::num::ParseIntError")]
+    Default,
+}
+
+#[test]
+fn test_variant_source_default_variant() {
+    let read = DefaultSourceError::new(DefaultSourceKind::Read, io::Error::other("disk"));
+    assert!(read.origin().is::<io::Error>());
+
+    let parse_err = "x".parse::<u8>().unwrap_err();
+    let parse = DefaultSourceError::new(DefaultSourceKind::Default, parse_err);
+    assert!(parse.origin().is::<std::num::ParseIntError>());
+}
+
+#[derive(KindError, Debug, Eq, PartialEq)]
+#[kind_error(source = "io::Error", name = "SameSourceError")]
+enum SameSourceKind {
+    #[kind_error(source = "io::Error")]
+    Read,
+    Write,
+}
+
+#[test]
+fn test_variant_source_homogeneous() {
+    let err = SameSourceError::new(SameSourceKind::Read, io::Error::other("disk"));
+    let origin: &io::Error = err.origin();
+    assert_eq!(origin.kind(), io::ErrorKind::Other);
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(source = "io::Error", name = "HiddenKindError", kind_fn = false)]
+enum HiddenKind {
+    #[kind_error(code = 1)]
+    Internal,
+}
+
+#[test]
+fn test_kind_fn_false() {
+    let err = HiddenKindError::new(HiddenKind::Internal, io::Error::other("err"));
+    assert_eq!(err.code(), 1);
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(source = "io::Error", name = "RetryError")]
+enum RetryKind {
+    Connect,
+}
+
+#[test]
+fn test_replace_source() {
+    let mut err = RetryError::new(RetryKind::Connect, io::Error::other("first attempt"));
+    let old = err.replace_source(io::Error::other("second attempt"));
+    assert_eq!(old.to_string(), "first attempt");
+    assert_eq!(err.origin().to_string(), "second attempt");
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(
+    source = "io::Error",
+    name = "SnakeNameError",
+    rename_all = "snake_case"
+)]
+enum SnakeNameKind {
+    NotFound,
+    HTTPTimeout { secs: u64 },
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(
+    source = "io::Error",
+    name = "CamelNameError",
+    rename_all = "camelCase",
+    is_fns = true,
+    as_fns = true
+)]
+enum CamelNameKind {
+    NotFound,
+    HTTPRetry(u32),
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(
+    source = "io::Error",
+    name = "ScreamingNameError",
+    rename_all = "SCREAMING_SNAKE_CASE",
+    is_fns = true
+)]
+enum ScreamingNameKind {
+    NotFound,
+}
+
+#[test]
+fn test_rename_all() {
+    let err = SnakeNameError::new(SnakeNameKind::NotFound, io::Error::other("err"));
+    assert_eq!(err.kind_name(), "not_found");
+    let err = SnakeNameError::new(
+        SnakeNameKind::HTTPTimeout { secs: 1 },
+        io::Error::other("err"),
+    );
+    assert_eq!(err.kind_name(), "http_timeout");
+    let err = CamelNameError::new(CamelNameKind::NotFound, io::Error::other("err"));
+    assert_eq!(err.kind_name(), "notFound");
+    let err = ScreamingNameError::new(ScreamingNameKind::NotFound, io::Error::other("err"));
+    assert_eq!(err.kind_name(), "NOT_FOUND");
+}
+
+#[test]
+fn test_rename_all_methods() {
+    let err = CamelNameError::notFound(io::Error::other("err"));
+    assert!(err.isNotFound());
+    assert!(!err.isHttpRetry());
+    assert_eq!(err.asHttpRetry(), None);
+
+    let err = CamelNameError::httpRetry(io::Error::other("err"), 3);
+    assert!(err.isHttpRetry());
+    assert_eq!(err.asHttpRetry(), Some(&3));
+
+    let err = ScreamingNameError::NOT_FOUND(io::Error::other("err"));
+    assert!(err.IS_NOT_FOUND());
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(
+    source = "io::Error",
+    name = "LegacyError",
+    legacy_error_methods = true
+)]
+enum LegacyKind {
+    Timeout,
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_legacy_error_methods() {
+    let err = LegacyError::new(LegacyKind::Timeout, io::Error::other("slow"));
+    assert_eq!(err.description(), "Timeout");
+    assert_eq!(err.cause().unwrap().to_string(), "slow");
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(
+    source = "CustomError",
+    source_fn = false,
+    name = "DebugSourceError",
+    display = "{kind:?} caused by {source}",
+    source_display_fallback = true
+)]
+enum DebugSourceKind {
+    Broken,
+}
+
+#[test]
+fn test_source_display_fallback() {
+    let err = DebugSourceError::new(DebugSourceKind::Broken, CustomError);
+    assert_eq!(err.to_string(), "Broken caused by CustomError");
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(
+    source = "Box<dyn std::error::Error + Send + Sync>",
+    name = "BoxedSourceError"
+)]
+enum BoxedSourceKind {
+    Wrapped,
+}
+
+#[test]
+fn test_boxed_source_chain() {
+    let err = BoxedSourceError::new(BoxedSourceKind::Wrapped, io::Error::other("inner"));
+
+    let mut depth = 0;
+    let mut source = err.source();
+    while let Some(current) = source {
+        depth += 1;
+        source = current.source();
+    }
+    assert_eq!(depth, 1);
+    assert!(err.source().unwrap().downcast_ref::<io::Error>().is_some());
+}
+
+#[kinderror::kind_error_defaults(
+    source = "io::Error",
+    new_vis = "pub",
+    type_vis = "pub",
+    display = "shared: {kind:?}"
+)]
+mod shared_defaults {
+    use std::io;
+
+    use kinderror::KindError;
+
+    #[derive(KindError, Debug, Eq, PartialEq)]
+    #[kind_error(name = "FirstSharedError")]
+    pub enum FirstSharedKind {
+        First,
+    }
+
+    #[derive(KindError, Debug, Eq, PartialEq)]
+    #[kind_error(name = "SecondSharedError", display = "local: {kind:?}")]
+    pub enum SecondSharedKind {
+        Second,
+    }
+}
+
+#[test]
+fn test_kind_error_defaults() {
+    use shared_defaults::*;
+
+    let err = FirstSharedError::new(FirstSharedKind::First, io::Error::other("err"));
+    assert_eq!(err.to_string(), "shared: First");
+    let err = SecondSharedError::new(SecondSharedKind::Second, io::Error::other("err"));
+    assert_eq!(err.to_string(), "local: Second");
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(
+    source = "io::Error",
+    name = "DetailedError",
+    display = "{kind:?} failed",
+    display_detail = "{kind:?} failed because of {source} ({source:?})"
+)]
+enum DetailedKind {
+    Upload,
+}
+
+#[test]
+fn test_display_detail() {
+    let err = DetailedError::new(DetailedKind::Upload, io::Error::other("timeout"));
+    assert_eq!(err.to_string(), "Upload failed");
+    assert!(
+        err.detail()
+            .starts_with("Upload failed because of timeout (")
+    );
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(
+    source = "io::Error",
+    name = "MaybeSourcedError",
+    optional_source = true,
+    display = "{kind:?}: {source}"
+)]
+enum MaybeSourcedKind {
+    Invalid,
+    Io,
+}
+
+#[test]
+fn test_optional_source() {
+    let err = MaybeSourcedError::from_kind(MaybeSourcedKind::Invalid);
+    assert!(err.origin().is_none());
+    assert!(err.source().is_none());
+    assert_eq!(err.to_string(), "Invalid: ");
+
+    let err = MaybeSourcedError::new(MaybeSourcedKind::Io, Some(io::Error::other("disk")));
+    assert_eq!(err.origin().unwrap().to_string(), "disk");
+    assert!(err.source().is_some());
+    assert_eq!(err.to_string(), "Io: disk");
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(name = "DomainError", repr_transparent = true)]
+enum DomainKind {
+    EmptyName,
+    NegativeAge,
+}
+
+#[test]
+fn test_kind_only() {
+    let err = DomainError::new(DomainKind::NegativeAge);
+    assert_eq!(err.kind(), &DomainKind::NegativeAge);
+    assert!(err.source().is_none());
+    assert_eq!(err.to_string(), "error kind: NegativeAge");
+    assert_eq!(
+        std::mem::size_of::<DomainError>(),
+        std::mem::size_of::<DomainKind>()
+    );
+}
+
+#[test]
+fn test_variant_constructors() {
+    let read = MixedSourceError::new_read(io::Error::other("eof"));
+    assert_eq!(*read.kind(), MixedSourceKind::Read);
+    assert!(read.origin().is::<io::Error>());
+
+    let parse = MixedSourceError::new_parse("x".parse::<u8>().unwrap_err());
+    assert_eq!(*parse.kind(), MixedSourceKind::Parse);
+    assert!(parse.origin().is::<std::num::ParseIntError>());
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(name = "CatchAllError", catch_all = true)]
+enum CatchAllKind {
+    Parse,
+    Read,
+}
+
+fn parse_catch_all(s: &str) -> Result<u8, CatchAllError> {
+    s.parse::<u8>()
+        .map_err(|err| (CatchAllKind::Parse, err).into())
+}
+
+#[test]
+fn test_catch_all() {
+    let err = parse_catch_all("x").unwrap_err();
+    assert_eq!(*err.kind(), CatchAllKind::Parse);
+    assert!(err.origin().is::<std::num::ParseIntError>());
+
+    let err = CatchAllError::boxed(CatchAllKind::Read, io::Error::other("eof"));
+    assert!(err.source().unwrap().is::<io::Error>());
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(source = "io::Error", name = "CompactError", repr = "compact")]
+enum CompactKind {
+    NotFound,
+    Io,
+}
+
+#[test]
+fn test_repr_compact() {
+    let err = CompactError::from(CompactKind::NotFound);
+    assert_eq!(*err.kind(), CompactKind::NotFound);
+    assert!(err.origin().is_none());
+    assert!(err.source().is_none());
+
+    let err = CompactError::new(CompactKind::Io, Some(io::Error::other("disk")));
+    assert_eq!(err.origin().unwrap().to_string(), "disk");
+    assert!(err.source().unwrap().is::<io::Error>());
+    assert_eq!(
+        std::mem::size_of::<CompactError>(),
+        2 * std::mem::size_of::<usize>()
+    );
+}
+
+#[derive(Debug)]
+struct LargeSource {
+    payload: [u8; 256],
+}
+
+impl Display for LargeSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "large source of {} bytes", self.payload.len())
+    }
+}
+
+impl std::error::Error for LargeSource {}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(
+    source = "LargeSource",
+    name = "HeapSourceError",
+    box_source = true,
+    get_ref = true,
+    into_parts = true
+)]
+enum HeapSourceKind {
+    Overflow,
+}
+
+#[test]
+fn test_box_source() {
+    let mut err = HeapSourceError::new(HeapSourceKind::Overflow, LargeSource { payload: [1; 256] });
+    assert_eq!(err.origin().payload[0], 1);
+    assert!(err.source().unwrap().is::<LargeSource>());
+    assert!(std::mem::size_of::<HeapSourceError>() <= 2 * std::mem::size_of::<usize>());
+
+    let previous = err.replace_source(LargeSource { payload: [2; 256] });
+    assert_eq!(previous.payload[0], 1);
+    assert_eq!(err.origin().payload[0], 2);
+}
+
+#[derive(KindError, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[kind_error(
+    source = "io::Error",
+    name = "ThinError",
+    boxed = true,
+    into_parts = true,
+    map_fns = true,
+    io_clone = true,
+    ord_by_kind = true,
+    display = "{kind:?}: {source}"
+)]
+enum ThinKind {
+    Read,
+    Write,
+}
+
+#[test]
+fn test_boxed() {
+    assert_eq!(
+        std::mem::size_of::<ThinError>(),
+        std::mem::size_of::<usize>()
+    );
+
+    let err = ThinError::new(ThinKind::Write, io::Error::other("full"));
+    assert_eq!(*err.kind(), ThinKind::Write);
+    assert_eq!(err.origin().to_string(), "full");
+    assert_eq!(err.to_string(), "Write: full");
+    assert!(err.clone() > ThinError::new(ThinKind::Read, io::Error::other("eof")));
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(source = "io::Error", name = "BudgetError", max_size = 24)]
+enum BudgetKind {
+    Exceeded,
+}
+
+#[test]
+fn test_max_size() {
+    assert!(std::mem::size_of::<BudgetError>() <= 24);
+    let err = BudgetError::new(BudgetKind::Exceeded, io::Error::other("budget"));
+    assert_eq!(err.origin().to_string(), "budget");
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(source = "io::Error", name = "TracedError", backtrace = true)]
+enum TracedKind {
+    Lost,
+}
+
+#[test]
+fn test_backtrace() {
+    let err = TracedError::new(TracedKind::Lost, io::Error::other("lost"));
+    assert_eq!(
+        err.backtrace().status(),
+        std::backtrace::BacktraceStatus::Captured
+    );
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(
+    source = "io::Error",
+    name = "QuietError",
+    backtrace = true,
+    backtrace_policy = "env:KINDERROR_TEST_UNSET_BACKTRACE"
+)]
+enum QuietKind {
+    Lost,
+}
+
+#[test]
+fn test_backtrace_policy() {
+    let err = QuietError::new(QuietKind::Lost, io::Error::other("lost"));
+    assert_eq!(
+        err.backtrace().status(),
+        std::backtrace::BacktraceStatus::Disabled
+    );
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(source = "io::Error", name = "LocatedError", location = true)]
+enum LocatedKind {
+    Missing,
+}
+
+#[test]
+fn test_location() {
+    let line = line!() + 1;
+    let err = LocatedError::new(LocatedKind::Missing, io::Error::other("gone"));
+    assert_eq!(err.location().file(), file!());
+    assert_eq!(err.location().line(), line);
+    assert!(
+        err.to_string()
+            .ends_with(&format!(", at {}", err.location()))
+    );
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(
+    source = "io::Error",
+    name = "FileError",
+    extra_fields = "path: std::path::PathBuf, attempt: u32",
+    display = "{kind:?} {path:?} (attempt {attempt}): {source}"
+)]
+enum FileKind {
+    Open,
+}
+
+#[test]
+fn test_extra_fields() {
+    let err = FileError::new(
+        FileKind::Open,
+        io::Error::other("denied"),
+        "/etc/shadow".into(),
+        3,
+    );
+    assert_eq!(err.path(), std::path::Path::new("/etc/shadow"));
+    assert_eq!(*err.attempt(), 3);
+    assert_eq!(err.to_string(), "Open \"/etc/shadow\" (attempt 3): denied");
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(source = "io::Error", name = "ContextError", context = true)]
+enum ContextKind {
+    Upload,
+}
+
+#[test]
+fn test_context() {
+    let err = ContextError::new(ContextKind::Upload, io::Error::other("reset"))
+        .with_context("bucket", "avatars")
+        .with_context("attempt", 2);
+    assert_eq!(
+        err.context(),
+        [
+            ("bucket", "avatars".to_string()),
+            ("attempt", "2".to_string())
+        ]
+    );
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(
+    source = "io::Error",
+    name = "MessageError",
+    message = true,
+    display = "{kind:?}: {message}"
+)]
+enum MessageKind {
+    Fetch,
+}
+
+#[test]
+fn test_message() {
+    let err = MessageError::new_with_message(
+        MessageKind::Fetch,
+        io::Error::other("timeout"),
+        format!("fetching {}", "users.json"),
+    );
+    assert_eq!(err.message(), "fetching users.json");
+    assert_eq!(err.to_string(), "Fetch: fetching users.json");
+
+    let err = MessageError::new(MessageKind::Fetch, io::Error::other("timeout"));
+    assert_eq!(err.message(), "");
+}
+
+fn fixed_clock() -> std::time::SystemTime {
+    std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000)
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(
+    source = "io::Error",
+    name = "ClockedError",
+    timestamp = true,
+    clock_fn = "fixed_clock"
+)]
+enum ClockedKind {
+    Queued,
+}
+
+#[test]
+fn test_clock_fn() {
+    let err = ClockedError::new(ClockedKind::Queued, io::Error::other("late"));
+    assert_eq!(err.occurred_at(), fixed_clock());
+
+    let before = std::time::SystemTime::now();
+    let err = TimestampError::new(TimestampKind::First, io::Error::other("err"));
+    assert!(err.occurred_at() >= before);
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(source = "io::Error", name = "BatchError", related = true)]
+enum BatchKind {
+    Partial,
+}
+
+#[test]
+fn test_related() {
+    let mut err = BatchError::new(BatchKind::Partial, io::Error::other("first"));
+    err.push_related(io::Error::other("second"));
+    err.push_related("third");
+
+    let related = err
+        .related()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    assert_eq!(related, ["second", "third"]);
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(source = "io::Error", name = "HelpfulError", display = "{kind:?}")]
+enum HelpfulKind {
+    #[kind_error(help = "check that the config file exists")]
+    MissingConfig,
+    Unknown,
+}
+
+#[test]
+fn test_help() {
+    let err = HelpfulError::new(HelpfulKind::MissingConfig, io::Error::other("enoent"));
+    assert_eq!(err.help(), Some("check that the config file exists"));
+    assert_eq!(err.to_string(), "MissingConfig");
+    assert_eq!(
+        format!("{:#}", err),
+        "MissingConfig\nhelp: check that the config file exists"
+    );
+
+    let err = HelpfulError::new(HelpfulKind::Unknown, io::Error::other("?"));
+    assert_eq!(err.help(), None);
+    assert_eq!(format!("{:#}", err), "Unknown");
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(source = "io::Error", name = "RunbookError", termination = true)]
+enum RunbookKind {
+    #[kind_error(docs_url = "https://docs.example.com/errors/E102")]
+    QuotaExceeded,
+    Other,
+}
+
+#[test]
+fn test_docs_url() {
+    let err = RunbookError::new(RunbookKind::QuotaExceeded, io::Error::other("quota"));
+    assert_eq!(err.docs_url(), Some("https://docs.example.com/errors/E102"));
+
+    let err = RunbookError::new(RunbookKind::Other, io::Error::other("?"));
+    assert_eq!(err.docs_url(), None);
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(
+    name = "LayeredError",
+    catch_all = true,
+    chain = true,
+    kind_chain = true
+)]
+enum LayeredKind {
+    Storage,
+    Request,
+}
+
+#[test]
+fn test_kind_chain() {
+    let storage = LayeredError::boxed(LayeredKind::Storage, io::Error::other("disk"));
+    let request = LayeredError::boxed(LayeredKind::Request, storage);
+
+    let kinds = request.kind_chain().collect::<Vec<_>>();
+    assert_eq!(kinds, [&LayeredKind::Request, &LayeredKind::Storage]);
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(
+    source = "io::Error",
+    name = "TupleError",
+    from_tuple = true,
+    get_ref = true,
+    into_fns = true,
+    map_fns = true,
+    into_parts = true
+)]
+enum TupleKind {
+    Read,
+}
+
+fn read_tuple() -> Result<(), TupleError> {
+    Err((TupleKind::Read, io::Error::other("eof")).into())
+}
+
+#[test]
+fn test_from_tuple() {
+    let err = read_tuple().unwrap_err();
+    assert_eq!(*err.kind(), TupleKind::Read);
+    assert_eq!(err.origin().to_string(), "eof");
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(
+    source = "io::Error",
+    name = "DefaultKindError",
+    default_kind = "DefaultKind::Io"
+)]
+enum DefaultKind {
+    Io,
+    Other,
+}
+
+fn read_default_kind() -> Result<(), DefaultKindError> {
+    Err(io::Error::other("eof"))?
+}
+
+#[test]
+fn test_default_kind_from_source() {
+    let err = read_default_kind().unwrap_err();
+    assert_eq!(*err.kind(), DefaultKind::Io);
+    assert_eq!(err.origin().to_string(), "eof");
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(
+    source = "io::Error",
+    name = "SourceDefaultError",
+    source_default = "io::Error::other(\"unknown\")"
+)]
+enum SourceDefaultKind {
+    Missing,
+}
+
+#[test]
+fn test_source_default() {
+    let err = SourceDefaultError::from_kind(SourceDefaultKind::Missing);
+    assert_eq!(*err.kind(), SourceDefaultKind::Missing);
+    assert_eq!(err.origin().to_string(), "unknown");
+
+    let err: SourceDefaultError = SourceDefaultKind::Missing.into();
+    assert_eq!(err.origin().to_string(), "unknown");
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(source = "io::Error", name = "ShortcutError")]
+enum ShortcutKind {
+    NotFound,
+    Retry(u32),
+    Moved {
+        to: String,
+    },
+    Match,
+    // Clashes with `kind()`, so its constructor is named by the alias
+    #[kind_error(alias = "of_kind", alias = "with_kind")]
+    Kind,
+}
+
+#[test]
+fn test_variant_ctors() {
+    let err = ShortcutError::not_found(io::Error::other("gone"));
+    assert_eq!(*err.kind(), ShortcutKind::NotFound);
+    assert_eq!(err.origin().to_string(), "gone");
+
+    let err = ShortcutError::retry(io::Error::other("busy"), 3);
+    assert_eq!(*err.kind(), ShortcutKind::Retry(3));
+
+    let err = ShortcutError::moved(io::Error::other("moved"), "elsewhere".to_string());
+    assert_eq!(
+        *err.kind(),
+        ShortcutKind::Moved {
+            to: "elsewhere".to_string()
+        }
+    );
+
+    let err = ShortcutError::r#match(io::Error::other("match"));
+    assert_eq!(*err.kind(), ShortcutKind::Match);
+
+    let err = ShortcutError::of_kind(io::Error::other("kind"));
+    assert_eq!(*err.kind(), ShortcutKind::Kind);
+
+    #[allow(deprecated)]
+    let err = ShortcutError::with_kind(io::Error::other("kind"));
+    assert_eq!(*err.kind(), ShortcutKind::Kind);
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(source = "io::Error", name = "RenamedError", location = true)]
+enum RenamedKind {
+    #[kind_error(alias = "missing", alias = "absent")]
+    NotFound,
+    #[kind_error(alias = "busy")]
+    Retry(u32),
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_variant_ctor_alias() {
+    let err = RenamedError::missing(io::Error::other("gone"));
+    assert_eq!(*err.kind(), RenamedKind::NotFound);
+    assert_eq!(err.origin().to_string(), "gone");
+    // The caller of the alias is reported, not the constructor it delegates to
+    assert_eq!(err.location().line(), line!() - 4);
+
+    let err = RenamedError::absent(io::Error::other("gone"));
+    assert_eq!(*err.kind(), RenamedKind::NotFound);
+
+    let err = RenamedError::busy(io::Error::other("busy"), 3);
+    assert_eq!(*err.kind(), RenamedKind::Retry(3));
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(source = "io::Error", name = "WideError", kind_into = true)]
+enum WideKind {
+    Parse,
+    Net,
+}
+
+enum ParseStage {
+    Header,
+}
+
+impl From<ParseStage> for WideKind {
+    fn from(_: ParseStage) -> Self {
+        WideKind::Parse
+    }
+}
+
+#[test]
+fn test_kind_into() {
+    let err = WideError::new(ParseStage::Header, io::Error::other("bad header"));
+    assert_eq!(*err.kind(), WideKind::Parse);
+
+    let err = WideError::new(WideKind::Net, io::Error::other("reset"));
+    assert_eq!(*err.kind(), WideKind::Net);
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(
+    source = "io::Error",
+    name = "BuiltError",
+    builder = true,
+    message = true,
+    context = true,
+    extra_fields = "attempts: u32"
+)]
+enum BuiltKind {
+    Upload,
+}
+
+#[test]
+fn test_builder() {
+    let err = BuiltError::builder()
+        .kind(BuiltKind::Upload)
+        .source(io::Error::other("timeout"))
+        .attempts(3)
+        .message("upload failed")
+        .context("bucket", "logs")
+        .build()
+        .unwrap();
+    assert_eq!(*err.kind(), BuiltKind::Upload);
+    assert_eq!(err.origin().to_string(), "timeout");
+    assert_eq!(*err.attempts(), 3);
+    assert_eq!(err.message(), "upload failed");
+    assert_eq!(err.context(), &[("bucket", "logs".to_string())]);
+
+    assert!(
+        BuiltError::builder()
+            .kind(BuiltKind::Upload)
+            .build()
+            .is_none()
+    );
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(
+    source = "io::Error",
+    name = "NoNewError",
+    new_fn = false,
+    default_kind = "NoNewKind::Io"
+)]
+enum NoNewKind {
+    Io,
+}
+
+impl NoNewError {
+    fn new(message: &str) -> Self {
+        io::Error::other(message.to_string()).into()
+    }
+}
+
+#[test]
+fn test_new_fn_disabled() {
+    let err = NoNewError::new("smart");
+    assert_eq!(*err.kind(), NoNewKind::Io);
+    assert_eq!(err.origin().to_string(), "smart");
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(
+    source = "io::Error",
+    name = "ClassifiedError",
+    classify_with = "classify_io"
+)]
+enum ClassifiedKind {
+    NotFound,
+    Other,
+}
+
+fn classify_io(err: &io::Error) -> ClassifiedKind {
+    match err.kind() {
+        io::ErrorKind::NotFound => ClassifiedKind::NotFound,
+        _ => ClassifiedKind::Other,
+    }
+}
+
+#[test]
+fn test_classify_with() {
+    let err = ClassifiedError::from_source(io::Error::from(io::ErrorKind::NotFound));
+    assert_eq!(*err.kind(), ClassifiedKind::NotFound);
+
+    let err = ClassifiedError::from_source(io::Error::other("other"));
+    assert_eq!(*err.kind(), ClassifiedKind::Other);
+}
+
+#[derive(Debug)]
+struct DiskFull;
+
+impl From<DiskFull> for io::Error {
+    fn from(_: DiskFull) -> Self {
+        io::Error::other("disk full")
+    }
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(source = "io::Error", name = "VariantFromError")]
+enum VariantFromKind {
+    #[kind_error(from = "DiskFull")]
+    Storage,
+    #[kind_error(from = "io::ErrorKind")]
+    Io,
+}
+
+#[test]
+fn test_variant_from() {
+    let err = VariantFromError::from(DiskFull);
+    assert_eq!(*err.kind(), VariantFromKind::Storage);
+    assert_eq!(err.origin().to_string(), "disk full");
+
+    let err = VariantFromError::from(io::ErrorKind::NotFound);
+    assert_eq!(*err.kind(), VariantFromKind::Io);
+    assert_eq!(err.origin().kind(), io::ErrorKind::NotFound);
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(
+    source = "Box<dyn std::error::Error + Send + Sync>",
+    name = "ServiceError"
+)]
+enum ServiceKind {
+    Unavailable,
+}
+
+#[test]
+fn test_wrap() {
+    let lower = TupleError::new(TupleKind::Read, io::Error::other("eof"));
+    let err = ServiceError::wrap(ServiceKind::Unavailable, lower);
+    assert_eq!(*err.kind(), ServiceKind::Unavailable);
+
+    let lower = err.origin().downcast_ref::<TupleError>().unwrap();
+    assert_eq!(*lower.kind(), TupleKind::Read);
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(name = "GlueError", catch_all = true, default_kind = "GlueKind::Other")]
+enum GlueKind {
+    Other,
+}
+
+#[test]
+fn test_other() {
+    let err = GlueError::other("unexpected response");
+    assert_eq!(*err.kind(), GlueKind::Other);
+    assert_eq!(err.origin().to_string(), "unexpected response");
+
+    let err = GlueError::other(io::Error::other("reset"));
+    assert_eq!(err.origin().to_string(), "reset");
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(source = "ConstSource", name = "ConstFnsError", const_fns = true)]
+enum ConstFnsKind {
+    Overflow,
+}
+
+#[derive(Debug, PartialEq)]
+struct ConstSource;
+
+impl Display for ConstSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("const source")
+    }
+}
+
+impl std::error::Error for ConstSource {}
+
+const PREBUILT: ConstFnsError = ConstFnsError::new(ConstFnsKind::Overflow, ConstSource);
+const PREBUILT_KIND: &ConstFnsKind = PREBUILT.kind();
+
+#[test]
+fn test_const_fns() {
+    assert_eq!(*PREBUILT_KIND, ConstFnsKind::Overflow);
+    assert_eq!(*PREBUILT.origin(), ConstSource);
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(
+    source = "io::Error",
+    name = "ColdError",
+    cold_ctors = true,
+    location = true,
+    default_kind = "ColdKind::Io"
+)]
+enum ColdKind {
+    Io,
+}
+
+#[test]
+fn test_cold_ctors() {
+    let err = ColdError::new(ColdKind::Io, io::Error::other("cold"));
+    assert_eq!(err.location().line(), line!() - 1);
+
+    let err = ColdError::from(io::Error::other("cold"));
+    assert_eq!(*err.kind(), ColdKind::Io);
+}
+
+#[test]
+fn test_into_parts() {
+    let err = TupleError::new(TupleKind::Read, io::Error::other("eof"));
+    let (kind, source) = err.into_parts();
+    assert_eq!(kind, TupleKind::Read);
+    assert_eq!(source.to_string(), "eof");
+
+    let err = ThinError::new(ThinKind::Write, io::Error::other("thin"));
+    let (kind, source) = err.into_parts();
+    assert_eq!(kind, ThinKind::Write);
+    assert_eq!(source.to_string(), "thin");
+
+    let err = HeapSourceError::new(HeapSourceKind::Overflow, LargeSource { payload: [2; 256] });
+    let (_, source) = err.into_parts();
+    assert_eq!(source.payload[0], 2);
+}
+
+#[test]
+fn test_into_source() {
+    let err = TupleError::new(TupleKind::Read, io::Error::other("eof"));
+    assert_eq!(err.into_source().to_string(), "eof");
+
+    let err = TupleError::new(TupleKind::Read, io::Error::other("eof"));
+    assert_eq!(err.into_kind(), TupleKind::Read);
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(
+    source = "io::Error",
+    name = "MutableError",
+    mut_fn_vis = "pub",
+    map_fns = true
+)]
+enum MutableKind {
+    Transient,
+    Permanent,
+}
+
+#[test]
+fn test_mut_accessors() {
+    let mut err = MutableError::new(MutableKind::Transient, io::Error::other("first"));
+    *err.kind_mut() = MutableKind::Permanent;
+    *err.source_mut() = io::Error::other("second");
+    assert_eq!(*err.kind(), MutableKind::Permanent);
+    assert_eq!(err.origin().to_string(), "second");
+}
+
+#[test]
+fn test_get_ref() {
+    let mut err = TupleError::new(TupleKind::Read, io::Error::other("eof"));
+    assert_eq!(err.get_ref().unwrap().to_string(), "eof");
+    assert!(err.get_mut().unwrap().is::<io::Error>());
+
+    let mut err = HeapSourceError::new(HeapSourceKind::Overflow, LargeSource { payload: [3; 256] });
+    let source = err
+        .get_mut()
+        .unwrap()
+        .downcast_mut::<LargeSource>()
+        .unwrap();
+    source.payload[0] = 4;
+    assert_eq!(err.origin().payload[0], 4);
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(
+    source = "String",
+    source_fn = false,
+    name = "TextTupleError",
+    from_tuple = true
+)]
+enum TextTupleKind {
+    Read,
+}
+
+impl From<TupleKind> for TextTupleKind {
+    fn from(_: TupleKind) -> Self {
+        TextTupleKind::Read
+    }
+}
+
+#[test]
+fn test_map_kind_source() {
+    let err = MutableError::new(MutableKind::Transient, io::Error::other("first"))
+        .map_kind(|_| MutableKind::Permanent)
+        .map_source(|source| io::Error::other(format!("{source}, retried")));
+    assert_eq!(*err.kind(), MutableKind::Permanent);
+    assert_eq!(err.origin().to_string(), "first, retried");
+
+    let err =
+        ThinError::new(ThinKind::Read, io::Error::other("thin")).map_kind(|_| ThinKind::Write);
+    assert_eq!(*err.kind(), ThinKind::Write);
+
+    let err = TupleError::new(TupleKind::Read, io::Error::other("eof"));
+    let err: TextTupleError = err.map_source_into(|source| source.to_string());
+    assert_eq!(*err.kind(), TextTupleKind::Read);
+    assert_eq!(err.origin(), "eof");
+}
+
+#[derive(KindError, Debug, Clone, Copy, PartialEq)]
+#[kind_error(source = "io::Error", name = "CopyKindError", kind_by_value = true)]
+enum CopyKind {
+    Timeout,
+    Refused,
+}
+
+#[test]
+fn test_kind_by_value() {
+    let err = CopyKindError::new(CopyKind::Timeout, io::Error::other("slow"));
+    match err.kind() {
+        CopyKind::Timeout => {}
+        CopyKind::Refused => panic!("unexpected kind"),
+    }
+    assert_eq!(err.kind(), CopyKind::Timeout);
+}
+
+#[test]
+fn test_chain() {
+    let storage = LayeredError::boxed(LayeredKind::Storage, io::Error::other("disk"));
+    let request = LayeredError::boxed(LayeredKind::Request, storage);
+
+    let messages = request
+        .chain()
+        .map(|err| err.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(messages.len(), 3);
+    assert_eq!(messages[2], "disk");
+}
+
+#[test]
+fn test_root_cause() {
+    let storage = LayeredError::boxed(LayeredKind::Storage, io::Error::other("disk"));
+    let request = LayeredError::boxed(LayeredKind::Request, storage);
+    assert!(request.root_cause().is::<io::Error>());
+
+    let single = LayeredError::boxed(LayeredKind::Storage, io::Error::other("disk"));
+    let plain = LayeredError::new(LayeredKind::Request, Box::new(single));
+    assert_eq!(plain.root_cause().to_string(), "disk");
+}
+
+#[test]
+fn test_downcast_source() {
+    let mut err = ServiceError::wrap(ServiceKind::Unavailable, io::Error::other("down"));
+    assert!(err.downcast_source_ref::<TupleError>().is_none());
+    assert_eq!(
+        err.downcast_source_ref::<io::Error>().unwrap().to_string(),
+        "down"
+    );
+    *err.downcast_source_mut::<io::Error>().unwrap() = io::Error::other("still down");
+
+    let err = err.downcast_source::<TupleError>().unwrap_err();
+    let source = err.downcast_source::<io::Error>().unwrap();
+    assert_eq!(source.to_string(), "still down");
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(source = "io::Error", name = "PayloadError", as_fns = true)]
+enum PayloadKind {
+    Field1 { text: String },
+    Range(u32, u32),
+    Empty,
+}
+
+#[test]
+fn test_as_fns() {
+    let err = PayloadError::new(
+        PayloadKind::Field1 {
+            text: "bad".to_string(),
+        },
+        io::Error::other("payload"),
+    );
+    assert_eq!(err.as_field1(), Some(&"bad".to_string()));
+    assert_eq!(err.as_range(), None);
+
+    let err = PayloadError::new(PayloadKind::Range(1, 2), io::Error::other("payload"));
+    assert_eq!(err.as_range(), Some((&1, &2)));
+    assert_eq!(err.as_field1(), None);
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(
+    source = "io::Error",
+    name = "PredicateError",
+    is_fns = true,
+    kind_is_fns = true
+)]
+enum PredicateKind {
+    Timeout,
+    Status(u16),
+}
+
+#[test]
+fn test_is_fns() {
+    let err = PredicateError::new(PredicateKind::Timeout, io::Error::other("slow"));
+    assert!(err.is_timeout());
+    assert!(!err.is_status());
+
+    assert!(PredicateKind::Status(503).is_status());
+    assert!(!PredicateKind::Status(503).is_timeout());
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(source = "io::Error", name = "ComparableError", eq_kind = true)]
+enum ComparableKind {
+    First,
+    Second,
+}
+
+#[test]
+fn test_eq_kind() {
+    let err = ComparableError::new(ComparableKind::First, io::Error::other("first"));
+    assert!(err == ComparableKind::First);
+    assert!(err != ComparableKind::Second);
+    assert!(ComparableKind::First == err);
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(
+    source = "io::Error",
+    name = "DelegatingError",
+    origin_fn_vis = "pub(crate)",
+    delegate = "pub fn raw_os_error(&self) -> Option<i32>; pub fn kind(&self) -> io::ErrorKind as io_kind"
+)]
+enum DelegatingKind {
+    Open,
+}
+
+#[test]
+fn test_delegate() {
+    let err = DelegatingError::new(DelegatingKind::Open, io::Error::from_raw_os_error(2));
+    assert_eq!(err.raw_os_error(), Some(2));
+    assert_eq!(err.io_kind(), io::ErrorKind::NotFound);
+    assert_eq!(*err.kind(), DelegatingKind::Open);
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(source = "io::Error", name = "ReportableError", as_ref_error = true)]
+enum ReportableKind {
+    Failed,
+}
+
+fn describe(err: impl AsRef<dyn std::error::Error + Send + Sync + 'static>) -> String {
+    err.as_ref().source().unwrap().to_string()
+}
+
+#[test]
+fn test_as_ref_error() {
+    let err = ReportableError::new(ReportableKind::Failed, io::Error::other("inner"));
+    assert_eq!(describe(err), "inner");
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(
+    source = "io::Error",
+    name = "RenamedFnError",
+    kind_fn_name = "class",
+    origin_fn_name = "inner",
+    new_fn_name = "create",
+    default_kind = "RenamedFnKind::Io"
+)]
+enum RenamedFnKind {
+    Io,
+}
+
+#[test]
+fn test_fn_names() {
+    let err = RenamedFnError::create(RenamedFnKind::Io, io::Error::other("renamed"));
+    assert_eq!(*err.class(), RenamedFnKind::Io);
+    assert_eq!(err.inner().to_string(), "renamed");
+
+    let err = RenamedFnError::from(io::Error::other("converted"));
+    assert_eq!(err.inner().to_string(), "converted");
+}
+
+mod field_config {
+    use kinderror::KindError;
+    use std::io;
+
+    #[derive(KindError, Debug, PartialEq)]
+    #[kind_error(
+        source = "io::Error",
+        name = "ClassError",
+        type_vis = "pub",
+        fields_vis = "pub(crate)",
+        kind_field = "class",
+        source_field = "cause"
+    )]
+    pub enum ClassKind {
+        Io,
+    }
+}
+
+#[test]
+fn test_field_config() {
+    use field_config::{ClassError, ClassKind};
+
+    let err = ClassError {
+        class: ClassKind::Io,
+        cause: io::Error::other("direct"),
+    };
+    let ClassError { class, cause } = &err;
+    assert_eq!(*class, ClassKind::Io);
+    assert_eq!(cause.to_string(), "direct");
+    assert!(format!("{err:?}").starts_with("ClassError { class: Io, cause:"));
+    assert_eq!(err.origin().to_string(), "direct");
+}
+
+#[derive(Debug)]
+pub struct UncloneableError;
+
+impl Display for UncloneableError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("uncloneable")
+    }
+}
+
+impl std::error::Error for UncloneableError {}
+
+#[derive(KindError, Debug, Clone, Copy, PartialEq)]
+#[kind_error(
+    source = "UncloneableError",
+    name = "FanOutError",
+    arc_source = true,
+    get_ref = true,
+    map_fns = true,
+    into_parts = true
+)]
+enum FanOutKind {
+    Failed,
+    Retried,
+}
+
+#[test]
+fn test_arc_source() {
+    let mut err = FanOutError::new(FanOutKind::Failed, UncloneableError);
+    assert!(err.get_mut().is_some());
+
+    let cloned = err.clone();
+    assert_eq!(cloned.kind(), &FanOutKind::Failed);
+    assert!(std::ptr::eq(err.origin(), cloned.origin()));
+    assert!(err.get_mut().is_none());
+    assert_eq!(err.source().unwrap().to_string(), "uncloneable");
+
+    let shared = cloned.shared();
+    let waiter = std::sync::Arc::clone(&shared);
+    assert_eq!(waiter.kind(), &FanOutKind::Failed);
+
+    let err = err.map_kind(|_| FanOutKind::Retried);
+    let (kind, source) = err.into_parts();
+    assert_eq!(kind, FanOutKind::Retried);
+    assert_eq!(std::sync::Arc::strong_count(&source), 2);
+}
+
+#[derive(KindError, Debug, Clone, PartialEq)]
+#[kind_error(
+    source = "String",
+    source_fn = false,
+    name = "DerivedError",
+    derive = "Clone, PartialEq"
+)]
+enum DerivedKind {
+    Invalid,
+}
+
+#[test]
+fn test_derive() {
+    let err = DerivedError::new(DerivedKind::Invalid, "bad input".to_string());
+    let cloned = err.clone();
+    assert_eq!(err, cloned);
+    assert_ne!(
+        err,
+        DerivedError::new(DerivedKind::Invalid, "other input".to_string())
+    );
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(
+    source = "io::Error",
+    name = "AlignedError",
+    struct_attrs = "#[repr(C, align(64))] #[doc = \"An aligned error\"]"
+)]
+enum AlignedKind {
+    Io,
+}
+
+#[test]
+fn test_struct_attrs() {
+    assert_eq!(std::mem::align_of::<AlignedError>(), 64);
+
+    let err = AlignedError::new(AlignedKind::Io, io::Error::other("aligned"));
+    assert_eq!(err.kind(), &AlignedKind::Io);
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(
+    source = "io::Error",
+    name = "SealedError",
+    fields_vis = "pub",
+    non_exhaustive = true
+)]
+enum SealedKind {
+    Io,
+}
+
+#[test]
+fn test_non_exhaustive() {
+    // Within the defining crate the struct can still be built and destructured
+    let err = SealedError {
+        kind: SealedKind::Io,
+        source: io::Error::other("sealed"),
+    };
+    let SealedError { kind, .. } = &err;
+    assert_eq!(kind, &SealedKind::Io);
+    assert_eq!(err.origin().to_string(), "sealed");
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(
+    source = "io::Error",
+    name = "HeededError",
+    must_use = true,
+    builder = true
+)]
+enum HeededKind {
+    Io,
+}
+
+#[test]
+fn test_must_use() {
+    let err = HeededError::builder()
+        .kind(HeededKind::Io)
+        .source(io::Error::other("heeded"))
+        .build()
+        .unwrap();
+    assert_eq!(err.kind(), &HeededKind::Io);
+}
+
+#[derive(KindError, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[kind_error(
+    source = "io::Error",
+    name = "DedupError",
+    cmp_by_kind = true,
+    ord_by_kind = true
+)]
+enum DedupKind {
+    Timeout,
+    Refused,
+}
+
+#[test]
+fn test_cmp_by_kind() {
+    let errors = [
+        DedupError::new(DedupKind::Refused, io::Error::other("first")),
+        DedupError::new(DedupKind::Timeout, io::Error::other("second")),
+        DedupError::new(DedupKind::Refused, io::Error::other("third")),
+    ];
+    assert_eq!(errors[0], errors[2]);
+
+    let unique = errors.iter().collect::<std::collections::HashSet<_>>();
+    assert_eq!(unique.len(), 2);
+
+    let mut sorted = errors.iter().collect::<Vec<_>>();
+    sorted.sort();
+    assert_eq!(sorted[0].kind(), &DedupKind::Timeout);
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(
+    source = "io::Error",
+    name = "TerseError",
+    debug = "{kind:?}: {source}"
+)]
+enum TerseKind {
+    Read,
+}
+
+#[test]
+fn test_debug_template() {
+    let err = TerseError::new(TerseKind::Read, io::Error::other("disk gone"));
+    assert_eq!(format!("{err:?}"), "Read: disk gone");
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(
+    source = "String",
+    source_fn = false,
+    name = "MaskedError",
+    auto_debug = false
+)]
+enum MaskedKind {
+    Auth,
+}
+
+impl Debug for MaskedError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MaskedError")
+            .field("kind", &self.kind)
+            .finish_non_exhaustive()
+    }
+}
+
+#[test]
+fn test_auto_debug_disabled() {
+    let err = MaskedError::new(MaskedKind::Auth, "hunter2".to_string());
+    assert_eq!(format!("{err:?}"), "MaskedError { kind: Auth, .. }");
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(
+    source = "io::Error",
+    name = "InnerReportError",
+    display = "inner {kind:?}"
+)]
+enum InnerReportKind {
+    Read,
+}
+
+#[derive(KindError, Debug, PartialEq)]
+#[kind_error(
+    source = "InnerReportError",
+    name = "OuterReportError",
+    display = "report {kind:?}",
+    debug_chain = true
+)]
+enum OuterReportKind {
+    Load,
+}
+
+#[test]
+fn test_debug_chain() {
+    let inner = InnerReportError::new(InnerReportKind::Read, io::Error::other("disk gone"));
+    let err = OuterReportError::new(OuterReportKind::Load, inner);
+
+    assert_eq!(
+        format!("{err:#?}"),
+        "report Load\n\nCaused by:\n    0: inner Read\n    1: disk gone"
+    );
+    assert!(
+        format!("{err:?}").starts_with("OuterReportError { kind: Load, source: InnerReportError {")
+    );
+}
+
+#[derive(KindError, Debug)]
+#[kind_error(
+    source = "S",
+    name = "GenericLayerError",
+    chain = true,
+    kind_chain = true
+)]
+enum GenericLayerKind<S> {
+    Outer,
+    #[allow(dead_code)]
+    Marker(std::marker::PhantomData<S>),
+}
+
+#[test]
+fn test_chain_generic_source() {
+    let err =
+        GenericLayerError::<io::Error>::new(GenericLayerKind::Outer, io::Error::other("inner"));
+    let messages = err.chain().map(|err| err.to_string()).collect::<Vec<_>>();
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[1], "inner");
+}
+
+#[test]
+fn test_root_cause_generic_source() {
+    let err =
+        GenericLayerError::<io::Error>::new(GenericLayerKind::Outer, io::Error::other("inner"));
+    assert_eq!(err.root_cause().to_string(), "inner");
+}
+
+#[test]
+fn test_kind_chain_generic_source() {
+    let inner =
+        GenericLayerError::<io::Error>::new(GenericLayerKind::Outer, io::Error::other("inner"));
+    let err =
+        GenericLayerError::<GenericLayerError<io::Error>>::new(GenericLayerKind::Outer, inner);
+    // Only layers of the exact same type are visited
+    assert_eq!(err.kind_chain().count(), 1);
+}
+
+#[derive(KindError, Debug, Clone, Copy, PartialEq)]
+#[kind_error(
+    source = "UncloneableError",
+    name = "TaggedFanOutError",
+    arc_source = true,
+    extra_fields = "tag: String"
+)]
+enum TaggedFanOutKind {
+    Failed,
+}
+
+#[test]
+fn test_arc_source_extra_fields() {
+    let err = TaggedFanOutError::new(
+        TaggedFanOutKind::Failed,
+        UncloneableError,
+        "tag".to_string(),
+    );
+    let cloned = err.clone();
+    assert_eq!(cloned.tag(), "tag");
+    assert!(std::ptr::eq(err.origin(), cloned.origin()));
+}
+
+#[cfg(feature = "serde")]
+mod serde_tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(KindError, Debug, PartialEq, Serialize, Deserialize)]
+    #[kind_error(
+        source = "io::Error",
+        name = "WireError",
+        serde = true,
+        extra_fields = "attempts: u32"
+    )]
+    enum WireKind {
+        #[kind_error(code = 503)]
+        Unavailable,
+        #[kind_error(code = 504)]
+        Timeout,
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let err = WireError::new(WireKind::Timeout, io::Error::other("reset"), 3);
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "kind": "Timeout",
+                "source": "reset",
+                "code": 504,
+                "attempts": 3,
+            })
+        );
+
+        let err: WireError = serde_json::from_value(json).unwrap();
+        assert_eq!(*err.kind(), WireKind::Timeout);
+        assert_eq!(err.origin().to_string(), "reset");
+        assert_eq!(*err.attempts(), 3);
+
+        let missing = serde_json::from_str::<WireError>(r#"{"kind":"Timeout","attempts":3}"#);
+        assert!(
+            missing
+                .unwrap_err()
+                .to_string()
+                .contains("missing field `source`")
+        );
+    }
+
+    #[derive(KindError, Debug, PartialEq, Serialize, Deserialize)]
+    #[kind_error(
+        source = "io::Error",
+        name = "MaybeWireError",
+        serde = true,
+        optional_source = true
+    )]
+    enum MaybeWireKind {
+        Invalid,
+    }
+
+    #[test]
+    fn test_serde_optional_source() {
+        let err = MaybeWireError::new(MaybeWireKind::Invalid, Some(io::Error::other("bad input")));
+        let json = serde_json::to_string(&err).unwrap();
+        assert_eq!(json, r#"{"kind":"Invalid","source":"bad input"}"#);
+        let err: MaybeWireError = serde_json::from_str(&json).unwrap();
+        assert_eq!(err.origin().unwrap().to_string(), "bad input");
+
+        let err = MaybeWireError::from_kind(MaybeWireKind::Invalid);
+        let json = serde_json::to_string(&err).unwrap();
+        assert_eq!(json, r#"{"kind":"Invalid","source":null}"#);
+        let err: MaybeWireError = serde_json::from_str(&json).unwrap();
+        assert!(err.origin().is_none());
+
+        // An optional source may be left out entirely
+        let err: MaybeWireError = serde_json::from_str(r#"{"kind":"Invalid"}"#).unwrap();
+        assert!(err.origin().is_none());
+    }
+
+    #[derive(KindError, Debug, PartialEq, Serialize, Deserialize)]
+    #[kind_error(
+        source = "io::Error",
+        name = "BatchWireError",
+        serde = true,
+        sources = true
+    )]
+    enum BatchWireKind {
+        Batch,
+    }
+
+    #[test]
+    fn test_serde_sources() {
+        let err = BatchWireError::new(
+            BatchWireKind::Batch,
+            vec![io::Error::other("first"), io::Error::other("second")],
+        );
+        let json = serde_json::to_string(&err).unwrap();
+        assert_eq!(json, r#"{"kind":"Batch","sources":["first","second"]}"#);
+
+        let err: BatchWireError = serde_json::from_str(&json).unwrap();
+        let sources = err
+            .sources()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(sources, ["first", "second"]);
+    }
+
+    #[derive(KindError, Debug, PartialEq, Serialize, Deserialize)]
+    #[kind_error(
+        source = "io::Error",
+        name = "AnnotatedWireError",
+        serde = true,
+        message = true,
+        context = true
+    )]
+    enum AnnotatedWireKind {
+        Upload,
+    }
+
+    #[test]
+    fn test_serde_message_context() {
+        let err = AnnotatedWireError::new_with_message(
+            AnnotatedWireKind::Upload,
+            io::Error::other("reset"),
+            "uploading avatar",
+        )
+        .with_context("bucket", "avatars");
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "kind": "Upload",
+                "source": "reset",
+                "message": "uploading avatar",
+                "context": [["bucket", "avatars"]],
+            })
+        );
+
+        // The context is only written, while the message is read back
+        let err: AnnotatedWireError = serde_json::from_value(json).unwrap();
+        assert_eq!(err.message(), "uploading avatar");
+        assert!(err.context().is_empty());
+    }
+}
+
+#[cfg(all(feature = "serde", feature = "schemars"))]
+mod json_schema_tests {
+    use schemars::JsonSchema;
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(KindError, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+    #[kind_error(
+        source = "io::Error",
+        name = "DocumentedError",
+        serde = true,
+        json_schema = true,
+        optional_source = true,
+        message = true,
+        extra_fields = "attempts: u32"
+    )]
+    enum DocumentedKind {
+        #[kind_error(code = 503)]
+        Unavailable,
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let schema = schemars::schema_for!(DocumentedError);
+        let schema = serde_json::to_value(&schema).unwrap();
+        assert_eq!(schema["title"], "DocumentedError");
+        assert_eq!(schema["type"], "object");
+
+        let mut properties = schema["properties"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        properties.sort();
+        assert_eq!(
+            properties,
+            ["attempts", "code", "kind", "message", "source"]
+        );
+        // The optional source, the code and the message may be left out
+        assert_eq!(schema["required"], serde_json::json!(["kind", "attempts"]));
+        assert_eq!(schema["properties"]["code"]["type"], "integer");
+    }
+}
+
+#[cfg(all(feature = "serde", feature = "utoipa"))]
+mod utoipa_tests {
+    use serde::{Deserialize, Serialize};
+    use utoipa::{PartialSchema, ToSchema};
+
+    use super::*;
+
+    #[derive(KindError, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+    #[kind_error(
+        source = "io::Error",
+        name = "ApiError",
+        serde = true,
+        utoipa = true,
+        context = true,
+        extra_fields = "attempts: u32"
+    )]
+    enum ApiKind {
+        #[kind_error(code = 503)]
+        Unavailable,
+    }
+
+    #[test]
+    fn test_utoipa_schema() {
+        assert_eq!(ApiError::name(), "ApiError");
+
+        let schema = serde_json::to_value(ApiError::schema()).unwrap();
+        assert_eq!(schema["type"], "object");
+
+        let mut properties = schema["properties"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        properties.sort();
+        assert_eq!(
+            properties,
+            ["attempts", "code", "context", "kind", "source"]
+        );
+        assert_eq!(
+            schema["required"],
+            serde_json::json!(["kind", "source", "attempts"])
+        );
+        assert_eq!(schema["properties"]["source"]["type"], "string");
+        assert_eq!(schema["properties"]["context"]["type"], "array");
+    }
+}
+
+#[cfg(feature = "miette")]
+mod miette_tests {
+    use miette::{Diagnostic, Severity};
+
+    use super::*;
+
+    #[derive(KindError, Debug)]
+    #[kind_error(source = "io::Error", name = "DiagnosedError", miette = true)]
+    enum DiagnosedKind {
+        #[kind_error(
+            code = 102,
+            help = "check that the config file exists",
+            docs_url = "https://docs.example.com/errors/E102",
+            severity = "warning"
+        )]
+        MissingConfig,
+        #[kind_error(code = 500)]
+        Unknown,
+    }
+
+    #[test]
+    fn test_miette_diagnostic() {
+        let err = DiagnosedError::new(DiagnosedKind::MissingConfig, io::Error::other("enoent"));
+        let diagnostic: &dyn Diagnostic = &err;
+        assert_eq!(diagnostic.code().unwrap().to_string(), "102");
+        assert_eq!(
+            diagnostic.help().unwrap().to_string(),
+            "check that the config file exists"
+        );
+        assert_eq!(
+            diagnostic.url().unwrap().to_string(),
+            "https://docs.example.com/errors/E102"
+        );
+        assert_eq!(diagnostic.severity(), Some(Severity::Warning));
+
+        let err = DiagnosedError::new(DiagnosedKind::Unknown, io::Error::other("?"));
+        let diagnostic: &dyn Diagnostic = &err;
+        assert_eq!(diagnostic.code().unwrap().to_string(), "500");
+        assert!(diagnostic.help().is_none());
+        assert!(diagnostic.url().is_none());
+        assert_eq!(diagnostic.severity(), None);
+    }
+}
+
+// defmt needs a global logger and its linker script to actually log, so this only checks the
+// generated impl compiles
+#[cfg(feature = "defmt")]
+mod defmt_tests {
+    use super::*;
+
+    #[derive(KindError, Debug, defmt::Format)]
+    #[kind_error(source = "io::Error", name = "LoggedError", defmt = true)]
+    enum LoggedKind {
+        Flash,
+    }
+
+    #[derive(KindError, Debug, defmt::Format)]
+    #[kind_error(
+        source = "CustomError",
+        name = "LoggedCustomError",
+        source_fn = false,
+        defmt = true
+    )]
+    enum LoggedCustomKind {
+        Sensor,
+    }
+
+    fn assert_format<T: defmt::Format>() {}
+
+    #[test]
+    fn test_defmt_format() {
+        assert_format::<LoggedError>();
+        assert_format::<LoggedCustomError>();
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary_tests {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    use super::*;
+
+    #[derive(Debug, Arbitrary)]
+    struct FuzzedSource {
+        offset: u16,
+    }
+
+    impl Display for FuzzedSource {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "bad byte at {}", self.offset)
+        }
+    }
+
+    impl std::error::Error for FuzzedSource {}
+
+    #[derive(KindError, Debug, Arbitrary)]
+    #[kind_error(source = "FuzzedSource", name = "FuzzedError", arbitrary = true)]
+    enum FuzzedKind {
+        Truncated,
+        Checksum(u32),
+        Header { version: u8 },
+    }
+
+    #[test]
+    fn test_arbitrary() {
+        let mut seen = [false; 3];
+        for byte in 0..=u8::MAX {
+            let data = [byte; 16];
+            let mut u = Unstructured::new(&data);
+            let err = FuzzedError::arbitrary(&mut u).unwrap();
+            let index = match err.kind() {
+                FuzzedKind::Truncated => 0,
+                FuzzedKind::Checksum(_) => 1,
+                FuzzedKind::Header { .. } => 2,
+            };
+            seen[index] = true;
+            assert!(err.origin().to_string().starts_with("bad byte at "));
+        }
+        assert_eq!(seen, [true; 3]);
+    }
+}
+
+#[cfg(feature = "tracing")]
+mod span_trace_tests {
+    use tracing_error::{ErrorLayer, SpanTraceStatus};
+    use tracing_subscriber::layer::SubscriberExt as _;
+
+    use super::*;
+
+    #[derive(KindError, Debug)]
+    #[kind_error(source = "io::Error", name = "TracedError", span_trace = true)]
+    enum TracedKind {
+        Load,
+    }
+
+    #[test]
+    fn test_span_trace() {
+        let subscriber = tracing_subscriber::registry().with(ErrorLayer::default());
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("load_config", path = "app.toml");
+            let _guard = span.enter();
+
+            let err = TracedError::new(TracedKind::Load, io::Error::other("enoent"));
+            assert_eq!(err.span_trace().status(), SpanTraceStatus::CAPTURED);
+            let trace = err.span_trace().to_string();
+            assert!(trace.contains("load_config"), "{trace}");
+            assert!(trace.contains("app.toml"), "{trace}");
+        });
+
+        // Without an ErrorLayer there is nothing to capture
+        let err = TracedError::new(TracedKind::Load, io::Error::other("enoent"));
+        assert_ne!(err.span_trace().status(), SpanTraceStatus::CAPTURED);
+    }
+}